@@ -0,0 +1,39 @@
+//! A Cloudflare Workers Cron Trigger claiming one account's Genshin Impact
+//! daily reward through the `fetch` backend. `wrangler.toml` alongside has the
+//! build and the schedule, `worker.js` the scheduled handler calling in here.
+
+use hoyo_checkin_rs::client::{Fetch, HttpClient};
+use http::header::{CONTENT_TYPE, COOKIE, ORIGIN, REFERER};
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+const SIGN: &str = "https://sg-hk4e-api.hoyolab.com/event/sol/sign?lang=en-us";
+const ACT_ID: &str = "e202102251931481";
+/// HoYoLAB's retcode for a reward that was already claimed today.
+const ALREADY_SIGNED: i64 = -5003;
+
+/// Claims today's reward with `cookies` as in a `Cookie` header, e.g.
+/// `ltuid_v2=...; ltoken_v2=...`, and returns HoYoLAB's message.
+#[wasm_bindgen]
+pub async fn check_in(cookies: String) -> Result<String, JsError> {
+    let request = http::Request::post(SIGN)
+        .header(CONTENT_TYPE, "application/json")
+        .header(COOKIE, cookies.trim())
+        .header(ORIGIN, "https://act.hoyolab.com")
+        .header(REFERER, "https://act.hoyolab.com/")
+        .body(serde_json::to_vec(&json!({ "act_id": ACT_ID }))?)?;
+    let response = Fetch
+        .execute(request)
+        .await
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    let body: Value = serde_json::from_slice(response.body())?;
+
+    match body["retcode"].as_i64() {
+        Some(0 | ALREADY_SIGNED) => Ok(body["message"].as_str().unwrap_or("OK").to_string()),
+        _ => Err(JsError::new(&format!(
+            "HTTP {}: {}",
+            response.status(),
+            body
+        ))),
+    }
+}
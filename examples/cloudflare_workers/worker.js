@@ -0,0 +1,11 @@
+// The Worker's entry point: runs the check-in on every Cron Trigger, with the
+// account's cookies from the HOYOLAB_COOKIES secret.
+import init, { check_in } from "./pkg/cloudflare_workers.js";
+import wasm from "./pkg/cloudflare_workers_bg.wasm";
+
+export default {
+  async scheduled(controller, env, ctx) {
+    await init({ module_or_path: wasm });
+    console.log(await check_in(env.HOYOLAB_COOKIES));
+  },
+};
@@ -0,0 +1,71 @@
+//! `audit_log`: a line per HoYoLAB request and how it was answered, to look
+//! back on when an account gets banned or the API changes. Cookies are
+//! redacted, so the log can be shared.
+
+use crate::{
+    client::{self, Request, Response},
+    clock, redact_headers,
+};
+use serde_json::{json, Value};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+use tracing::debug;
+
+static LOG: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Starts appending requests to the file at `path`.
+pub fn open(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    LOG.set(Mutex::new(file)).ok();
+    Ok(())
+}
+
+/// A request on its way, to be written once it is answered.
+pub struct Entry(Value);
+
+/// Notes down a request before it is sent, if the log is open.
+pub fn start(account: &str, request: &Request) -> Option<Entry> {
+    LOG.get()?;
+
+    Some(Entry(json!({
+        "time": clock::rfc3339(clock::unix_now()),
+        "account": account,
+        "method": request.method().as_str(),
+        "url": request.uri().to_string(),
+        "headers": redact_headers(request.headers()),
+    })))
+}
+
+impl Entry {
+    /// Writes the request with its response's status and retcode, or the
+    /// error it failed with.
+    pub fn finish(mut self, response: Result<&Response, &client::Error>) {
+        let Some(log) = LOG.get() else {
+            return;
+        };
+
+        let outcome = match response {
+            Ok(response) => json!({
+                "status": response.status().as_u16(),
+                "retcode": serde_json::from_slice::<Value>(response.body())
+                    .ok()
+                    .and_then(|body| body["retcode"].as_i64()),
+            }),
+            Err(e) => json!({ "error": e.to_string() }),
+        };
+
+        if let (Some(entry), Value::Object(outcome)) = (self.0.as_object_mut(), outcome) {
+            entry.extend(outcome);
+        }
+
+        let mut log = log.lock().expect("Audit log lock poisoned");
+
+        if let Err(e) = writeln!(log, "{}", self.0) {
+            debug!("Failed to write to the audit log: {}", e);
+        }
+    }
+}
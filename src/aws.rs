@@ -0,0 +1,285 @@
+//! `aws:` references: any string in the config of the form `aws:<name>` is
+//! replaced at startup with an AWS Secrets Manager secret (by ARN) or an SSM
+//! parameter (by name or ARN), for running on Lambda or ECS schedules
+//! without secrets in the config. A secret holding a JSON object, such as a
+//! key/value secret of cookies, is substituted as that object.
+
+use crate::{
+    client::{self, HttpClient, Request},
+    clock,
+};
+use hmac::{Hmac, KeyInit, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::env;
+
+const PREFIX: &str = "aws:";
+
+/// ECS serves the task role's credentials here, under the path in
+/// `AWS_CONTAINER_CREDENTIALS_RELATIVE_URI`.
+const ECS_CREDENTIALS: &str = "http://169.254.170.2";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    #[serde(rename = "Token")]
+    session_token: Option<String>,
+}
+
+impl Credentials {
+    /// From the environment, as Lambda provides them, or else from the ECS
+    /// container credentials endpoint.
+    async fn load<C: HttpClient>(client: &C) -> Result<Self, client::Error> {
+        if let (Ok(access_key_id), Ok(secret_access_key)) = (
+            env::var("AWS_ACCESS_KEY_ID"),
+            env::var("AWS_SECRET_ACCESS_KEY"),
+        ) {
+            return Ok(Self {
+                access_key_id,
+                secret_access_key,
+                session_token: env::var("AWS_SESSION_TOKEN").ok(),
+            });
+        }
+
+        let url = match (
+            env::var("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI"),
+            env::var("AWS_CONTAINER_CREDENTIALS_FULL_URI"),
+        ) {
+            (Ok(path), _) => format!("{}{}", ECS_CREDENTIALS, path),
+            (_, Ok(url)) => url,
+            _ => return Err("No AWS credentials in the environment".into()),
+        };
+        let mut request = http::Request::get(url);
+
+        if let Ok(token) = env::var("AWS_CONTAINER_AUTHORIZATION_TOKEN") {
+            request = request.header(http::header::AUTHORIZATION, token);
+        }
+
+        let response = client.execute(request.body(Vec::new())?).await?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "HTTP {} from the ECS credentials endpoint",
+                response.status()
+            )
+            .into());
+        }
+
+        Ok(serde_json::from_slice(response.body())?)
+    }
+}
+
+/// Whether `config` holds any `aws:` reference, i.e. needs `resolve`.
+pub fn references(config: &Value) -> bool {
+    match config {
+        Value::String(string) => string.starts_with(PREFIX),
+        Value::Array(values) => values.iter().any(references),
+        Value::Object(values) => values.values().any(references),
+        _ => false,
+    }
+}
+
+/// Replaces every `aws:` reference in `config` with what it names.
+pub async fn resolve<C: HttpClient>(client: &C, config: &mut Value) -> Result<(), client::Error> {
+    let mut found = Vec::new();
+    find(config, &mut found);
+
+    let credentials = Credentials::load(client).await?;
+
+    for value in found {
+        let Value::String(reference) = value else {
+            continue;
+        };
+        let name = &reference[PREFIX.len()..];
+        let secret = fetch(client, &credentials, name)
+            .await
+            .map_err(|e| format!("{}: {}", name, e))?;
+
+        *value = serde_json::from_str(&secret)
+            .ok()
+            .filter(Value::is_object)
+            .unwrap_or(Value::String(secret));
+    }
+
+    Ok(())
+}
+
+fn find<'a>(value: &'a mut Value, found: &mut Vec<&'a mut Value>) {
+    match value {
+        Value::String(string) if string.starts_with(PREFIX) => found.push(value),
+        Value::Array(values) => values.iter_mut().for_each(|value| find(value, found)),
+        Value::Object(values) => values.values_mut().for_each(|value| find(value, found)),
+        _ => {}
+    }
+}
+
+/// A Secrets Manager secret's string, or an SSM parameter's decrypted value.
+async fn fetch<C: HttpClient>(
+    client: &C,
+    credentials: &Credentials,
+    name: &str,
+) -> Result<String, client::Error> {
+    // arn:aws:<service>:<region>:<account>:..., or a bare parameter name.
+    let arn: Vec<&str> = name.splitn(6, ':').collect();
+    let region = match arn[..] {
+        ["arn", _, _, region, ..] => region.to_string(),
+        _ => env::var("AWS_REGION")
+            .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+            .map_err(|_| "No AWS_REGION to look the parameter up in")?,
+    };
+
+    if let ["arn", _, "secretsmanager", ..] = arn[..] {
+        let body = call(
+            client,
+            credentials,
+            &region,
+            "secretsmanager",
+            "secretsmanager.GetSecretValue",
+            json!({ "SecretId": name }),
+        )
+        .await?;
+
+        return body["SecretString"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "The secret has no SecretString".into());
+    }
+
+    let body = call(
+        client,
+        credentials,
+        &region,
+        "ssm",
+        "AmazonSSM.GetParameter",
+        json!({ "Name": name, "WithDecryption": true }),
+    )
+    .await?;
+
+    body["Parameter"]["Value"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "The parameter has no Value".into())
+}
+
+/// Calls an action of an AWS JSON API. `AWS_ENDPOINT_URL` replaces the
+/// regional endpoint, e.g. for LocalStack.
+async fn call<C: HttpClient>(
+    client: &C,
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    target: &str,
+    body: Value,
+) -> Result<Value, client::Error> {
+    let url = env::var("AWS_ENDPOINT_URL")
+        .unwrap_or_else(|_| format!("https://{}.{}.amazonaws.com/", service, region));
+    let mut request: Request = http::Request::post(url)
+        .header(http::header::CONTENT_TYPE, "application/x-amz-json-1.1")
+        .header("X-Amz-Target", target)
+        .body(serde_json::to_vec(&body)?)?;
+    let time = clock::rfc3339(clock::unix_now()).replace(['-', ':'], "");
+
+    sign(&mut request, credentials, region, service, &time)?;
+
+    let response = client.execute(request).await?;
+    let body: Value = serde_json::from_slice(response.body()).unwrap_or_default();
+
+    if !response.status().is_success() {
+        let message = body["message"].as_str().or(body["Message"].as_str());
+
+        return Err(match (body["__type"].as_str(), message) {
+            (Some(kind), Some(message)) => format!("{}: {}", kind, message).into(),
+            (Some(kind), None) => kind.to_string().into(),
+            _ => format!("HTTP {}", response.status()).into(),
+        });
+    }
+
+    Ok(body)
+}
+
+/// Signs the request with AWS Signature Version 4, at `time` as in
+/// `20150830T123600Z`. Every header it carries is signed.
+pub fn sign(
+    request: &mut Request,
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    time: &str,
+) -> Result<(), client::Error> {
+    let host = request
+        .uri()
+        .authority()
+        .ok_or("No host to sign")?
+        .to_string();
+    let headers = request.headers_mut();
+    headers.insert(http::header::HOST, host.parse()?);
+    headers.insert("x-amz-date", time.parse()?);
+
+    if let Some(token) = &credentials.session_token {
+        headers.insert("x-amz-security-token", token.parse()?);
+    }
+
+    let mut signed: Vec<(String, String)> = request
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            Ok((
+                name.as_str().to_string(),
+                value.to_str()?.trim().to_string(),
+            ))
+        })
+        .collect::<Result<_, client::Error>>()?;
+    signed.sort();
+
+    let names = signed
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical = format!(
+        "{}\n{}\n{}\n{}\n\n{}\n{}",
+        request.method(),
+        request.uri().path(),
+        request.uri().query().unwrap_or(""),
+        signed
+            .iter()
+            .map(|(name, value)| format!("{}:{}", name, value))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        names,
+        hex::encode(Sha256::digest(request.body())),
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", &time[..8], region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        time,
+        scope,
+        hex::encode(Sha256::digest(canonical.as_bytes()))
+    );
+    let key = [&time[..8], region, service, "aws4_request"].iter().fold(
+        format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+        |key, part| hmac(&key, part.as_bytes()),
+    );
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id,
+        scope,
+        names,
+        hex::encode(hmac(&key, string_to_sign.as_bytes()))
+    );
+
+    request
+        .headers_mut()
+        .insert(http::header::AUTHORIZATION, authorization.parse()?);
+    Ok(())
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,64 @@
+//! Gives up on a game for the rest of a run once its endpoints keep failing,
+//! instead of spending every remaining account's requests and retries on it.
+
+use crate::{
+    events,
+    games::Game,
+    report::{GameResult, RewardKind},
+    CheckinError,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use tracing::error;
+
+/// Default of the config's `circuit_breaker`.
+pub const THRESHOLD: u32 = 3;
+
+pub struct CircuitBreaker {
+    /// Failures in a row after which a game is skipped. 0 never skips.
+    threshold: u32,
+    /// Network failures in a row of each game's daily check-in, by game id.
+    failures: HashMap<&'static str, u32>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold,
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Whether the game is to be skipped for the remaining accounts.
+    pub fn is_open(&self, game: &dyn Game) -> bool {
+        self.threshold > 0 && self.failures.get(game.id()) >= Some(&self.threshold)
+    }
+
+    /// Counts a game's check-in results for one account. Only failures to
+    /// get a response at all count; API errors are up to the account.
+    pub fn record(&mut self, game: &dyn Game, results: &[GameResult]) {
+        let Some(daily) = results
+            .iter()
+            .find(|result| result.kind == RewardKind::Daily)
+        else {
+            return;
+        };
+
+        if !matches!(daily.error, Some(CheckinError::Network(_))) {
+            self.failures.remove(game.id());
+            return;
+        }
+
+        let failures = self.failures.entry(game.id()).or_default();
+        *failures += 1;
+
+        if *failures == self.threshold {
+            error!(
+                "{} endpoint down: failed for {} accounts in a row, skipping it for the rest of the run",
+                game.name(),
+                failures
+            );
+            events::emit("endpoint_down", json!({ "game": game.name() }));
+        }
+    }
+}
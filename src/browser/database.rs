@@ -0,0 +1,168 @@
+use super::Browser;
+use crate::cookies::is_hoyolab_host;
+use aes::Aes128;
+use cbc::cipher::{block_padding::Pkcs7, BlockModeDecrypt, KeyIvInit};
+use rusqlite::{Connection, OpenFlags};
+use sha1::Sha1;
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    path::Path,
+    process::Command,
+};
+use url::Url;
+
+/// Opens the database without locking it, so it can be read while the
+/// browser is running.
+fn open(path: &Path) -> Result<Connection, String> {
+    let failed = |e: &dyn std::fmt::Display| format!("Failed to open {}: {}", path.display(), e);
+
+    let path = path.canonicalize().map_err(|e| failed(&e))?;
+    let mut uri = Url::from_file_path(&path).map_err(|_| failed(&"not a file path"))?;
+    uri.set_query(Some("immutable=1"));
+
+    Connection::open_with_flags(
+        uri.as_str(),
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+    )
+    .map_err(|e| failed(&e))
+}
+
+/// Keeps the HoYoLAB cookies. Those set for hoyolab.com itself win over
+/// hoyoverse.com's.
+fn collect(mut rows: Vec<(String, String, String)>) -> BTreeMap<String, String> {
+    rows.retain(|(host, ..)| is_hoyolab_host(host.trim_start_matches('.')));
+    rows.sort_by_key(|(host, ..)| host.ends_with("hoyolab.com"));
+    rows.into_iter()
+        .map(|(_, name, value)| (name, value))
+        .collect()
+}
+
+pub fn firefox(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    let failed = |e: rusqlite::Error| format!("Failed to read {}: {}", path.display(), e);
+    let connection = open(path)?;
+    let mut statement = connection
+        .prepare("SELECT host, name, value FROM moz_cookies")
+        .map_err(failed)?;
+    let rows = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .and_then(Iterator::collect)
+        .map_err(failed)?;
+
+    Ok(collect(rows))
+}
+
+pub fn chromium(browser: Browser, path: &Path) -> Result<BTreeMap<String, String>, String> {
+    let failed = |e: rusqlite::Error| format!("Failed to read {}: {}", path.display(), e);
+    let connection = open(path)?;
+    // Version 24 started prefixing every decrypted value with a hash of its host.
+    let version: u32 = connection
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(failed)?
+        .parse()
+        .unwrap_or(0);
+    let mut statement = connection
+        .prepare("SELECT host_key, name, value, encrypted_value FROM cookies")
+        .map_err(failed)?;
+    let rows: Vec<(String, String, String, Vec<u8>)> = statement
+        .query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .and_then(Iterator::collect)
+        .map_err(failed)?;
+
+    // v10 and v11 values are under different keys, and a profile that
+    // switched keyrings holds both.
+    let mut keys = BTreeMap::new();
+    let mut cookies = Vec::new();
+
+    for (host, name, value, encrypted) in rows {
+        if !value.is_empty() || !is_hoyolab_host(host.trim_start_matches('.')) {
+            cookies.push((host, name, value));
+            continue;
+        }
+
+        let (scheme, ciphertext) = encrypted.split_at(encrypted.len().min(3));
+        let key: &[u8; 16] = match keys.entry(scheme.to_vec()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(encryption_key(browser, scheme)?),
+        };
+        let mut buffer = ciphertext.to_vec();
+        let plaintext = cbc::Decryptor::<Aes128>::new(key.into(), &[b' '; 16].into())
+            .decrypt_padded::<Pkcs7>(&mut buffer)
+            .map_err(|_| {
+                format!(
+                    "Failed to decrypt the {} cookie, the browser's key may have changed",
+                    name
+                )
+            })?;
+        let plaintext = if version >= 24 {
+            plaintext.get(32..).unwrap_or_default()
+        } else {
+            plaintext
+        };
+
+        cookies.push((host, name, String::from_utf8_lossy(plaintext).into_owned()));
+    }
+
+    Ok(collect(cookies))
+}
+
+/// Derives the AES key Chromium encrypts cookies with. `v10` values on Linux
+/// use a hardcoded password; otherwise it is kept in the system keyring.
+fn encryption_key(browser: Browser, scheme: &[u8]) -> Result<[u8; 16], String> {
+    let (password, iterations) = if cfg!(target_os = "macos") {
+        (
+            keyring_password(
+                "security",
+                &[
+                    "find-generic-password",
+                    "-w",
+                    "-s",
+                    &format!("{} Safe Storage", browser.name()),
+                ],
+            )?,
+            1003,
+        )
+    } else if cfg!(windows) {
+        return Err(format!(
+            "Reading {} cookies is not supported on Windows, use Firefox or cookies import --har",
+            browser.name()
+        ));
+    } else if scheme == b"v11" {
+        (
+            keyring_password(
+                "secret-tool",
+                &[
+                    "lookup",
+                    "application",
+                    &browser.name().to_ascii_lowercase(),
+                ],
+            )?,
+            1,
+        )
+    } else {
+        ("peanuts".to_string(), 1)
+    };
+
+    let mut key = [0; 16];
+    pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), b"saltysalt", iterations, &mut key);
+    Ok(key)
+}
+
+fn keyring_password(command: &str, args: &[&str]) -> Result<String, String> {
+    let failed = |e: &dyn std::fmt::Display| {
+        format!("Failed to read the browser's key with {}: {}", command, e)
+    };
+    let output = Command::new(command)
+        .args(args)
+        .output()
+        .map_err(|e| failed(&e))?;
+
+    if !output.status.success() {
+        return Err(failed(&output.status));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,150 @@
+#[cfg(feature = "browser-cookies")]
+mod database;
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy)]
+pub enum Browser {
+    Chrome,
+    Chromium,
+    Firefox,
+}
+
+impl Browser {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "chrome" => Ok(Self::Chrome),
+            "chromium" => Ok(Self::Chromium),
+            "firefox" => Ok(Self::Firefox),
+            _ => Err(format!(
+                "Unsupported browser \"{}\", expected \"chrome\", \"chromium\" or \"firefox\"",
+                name
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Chrome => "Chrome",
+            Self::Chromium => "Chromium",
+            Self::Firefox => "Firefox",
+        }
+    }
+
+    /// Where the browser keeps its profiles on this platform.
+    fn profiles_dir(self) -> Option<PathBuf> {
+        let home = env::var_os("HOME").map(PathBuf::from);
+
+        if cfg!(windows) {
+            let (var, dir) = match self {
+                Self::Chrome => ("LOCALAPPDATA", r"Google\Chrome\User Data"),
+                Self::Chromium => ("LOCALAPPDATA", r"Chromium\User Data"),
+                Self::Firefox => ("APPDATA", r"Mozilla\Firefox\Profiles"),
+            };
+
+            return env::var_os(var).map(|base| PathBuf::from(base).join(dir));
+        }
+
+        if cfg!(target_os = "macos") {
+            let dir = match self {
+                Self::Chrome => "Google/Chrome",
+                Self::Chromium => "Chromium",
+                Self::Firefox => "Firefox/Profiles",
+            };
+
+            return home.map(|home| home.join("Library/Application Support").join(dir));
+        }
+
+        let dir = match self {
+            Self::Chrome => ".config/google-chrome",
+            Self::Chromium => ".config/chromium",
+            Self::Firefox => ".mozilla/firefox",
+        };
+
+        home.map(|home| home.join(dir))
+    }
+
+    /// Cookie databases of every profile, the default profile first.
+    fn databases(self) -> Vec<PathBuf> {
+        let Some(dir) = self.profiles_dir() else {
+            return Vec::new();
+        };
+        let mut profiles: Vec<PathBuf> = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+
+        profiles.sort_by_key(|profile| {
+            let name = profile.file_name().unwrap_or_default().to_string_lossy();
+            !(name == "Default" || name.ends_with(".default-release"))
+        });
+
+        let files: &[&str] = match self {
+            // Chrome 96 moved the database into Network/.
+            Self::Chrome | Self::Chromium => &["Network/Cookies", "Cookies"],
+            Self::Firefox => &["cookies.sqlite"],
+        };
+
+        profiles
+            .iter()
+            .filter_map(|profile| {
+                files
+                    .iter()
+                    .map(|file| profile.join(file))
+                    .find(|path| path.is_file())
+            })
+            .collect()
+    }
+}
+
+/// Reads the HoYoLAB cookies stored by a browser, from `database` or else
+/// from the first profile that has any.
+pub fn cookies(
+    browser: Browser,
+    database: Option<&Path>,
+) -> Result<BTreeMap<String, String>, String> {
+    let databases = match database {
+        Some(database) => vec![database.to_path_buf()],
+        None => browser.databases(),
+    };
+
+    if databases.is_empty() {
+        return Err(format!(
+            "No {} profile found, pass the cookie database with --cookie-db",
+            browser.name()
+        ));
+    }
+
+    for database in &databases {
+        let cookies = read(browser, database)?;
+
+        if !cookies.is_empty() {
+            tracing::info!("Read cookies from {}", database.display());
+            return Ok(cookies);
+        }
+    }
+
+    Err(format!(
+        "No HoYoLAB cookies found in {}, log in to hoyolab.com with it first",
+        browser.name()
+    ))
+}
+
+#[cfg(feature = "browser-cookies")]
+fn read(browser: Browser, database: &Path) -> Result<BTreeMap<String, String>, String> {
+    match browser {
+        Browser::Chrome | Browser::Chromium => database::chromium(browser, database),
+        Browser::Firefox => database::firefox(database),
+    }
+}
+
+#[cfg(not(feature = "browser-cookies"))]
+fn read(_browser: Browser, _database: &Path) -> Result<BTreeMap<String, String>, String> {
+    Err("This binary was built without the browser-cookies feature".to_string())
+}
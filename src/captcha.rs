@@ -0,0 +1,239 @@
+use crate::{cli::HumanDuration, client::HttpClient};
+use http::{header::CONTENT_TYPE, Method};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    io::{self, Write},
+    time::Duration,
+};
+use tracing::debug;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum Provider {
+    #[serde(rename = "2captcha")]
+    TwoCaptcha,
+    #[serde(rename = "anti-captcha")]
+    AntiCaptcha,
+    #[serde(rename = "capsolver")]
+    Capsolver,
+}
+
+impl Provider {
+    fn base_url(self) -> &'static str {
+        match self {
+            Self::TwoCaptcha => "https://api.2captcha.com",
+            Self::AntiCaptcha => "https://api.anti-captcha.com",
+            Self::Capsolver => "https://api.capsolver.com",
+        }
+    }
+
+    fn task_type(self) -> &'static str {
+        match self {
+            Self::Capsolver => "GeeTestTaskProxyLess",
+            Self::TwoCaptcha | Self::AntiCaptcha => "GeeTestTaskProxyless",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TwoCaptcha => "2captcha",
+            Self::AntiCaptcha => "anti-captcha",
+            Self::Capsolver => "capsolver",
+        }
+    }
+}
+
+/// A captcha-solving service used when HoYoLAB answers a sign with a Geetest
+/// challenge.
+#[derive(Deserialize)]
+pub struct CaptchaSolver {
+    pub provider: Provider,
+    api_key: String,
+    /// How long to wait for a solution before giving up.
+    timeout: Option<HumanDuration>,
+}
+
+/// A solved Geetest v3 challenge, sent back to HoYoLAB as `x-rpc-*` headers.
+pub struct Solution {
+    pub challenge: String,
+    pub validate: String,
+    pub seccode: String,
+}
+
+impl Solution {
+    fn new(challenge: &str, validate: &str) -> Self {
+        Self {
+            challenge: challenge.to_string(),
+            validate: validate.to_string(),
+            seccode: format!("{}|jordan", validate),
+        }
+    }
+}
+
+/// A challenge solved by hand, set on the account so the next run can retry
+/// the sign with it.
+#[derive(Deserialize)]
+pub struct CaptchaToken {
+    challenge: String,
+    validate: String,
+}
+
+impl CaptchaToken {
+    pub fn solution(&self) -> Solution {
+        Solution::new(&self.challenge, &self.validate)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskResponse {
+    #[serde(default)]
+    error_id: i64,
+    error_description: Option<String>,
+    task_id: Option<Value>,
+    status: Option<String>,
+    solution: Option<TaskSolution>,
+}
+
+#[derive(Deserialize)]
+struct TaskSolution {
+    challenge: Option<String>,
+    validate: String,
+    seccode: Option<String>,
+}
+
+impl CaptchaSolver {
+    pub async fn solve<C: HttpClient>(
+        &self,
+        client: &C,
+        gt: &str,
+        challenge: &str,
+    ) -> Result<Solution, String> {
+        let created: TaskResponse = self
+            .call(
+                client,
+                "createTask",
+                json!({
+                    "clientKey": self.api_key,
+                    "task": {
+                        "type": self.provider.task_type(),
+                        "websiteURL": "https://act.hoyolab.com/",
+                        "gt": gt,
+                        "challenge": challenge,
+                    },
+                }),
+            )
+            .await?;
+
+        let task_id = created
+            .task_id
+            .ok_or("Captcha solver did not return a task id")?;
+        // Counted rather than timed, as wasm hosts have no monotonic clock.
+        let polls = self
+            .timeout
+            .map_or(Duration::from_secs(120), |timeout| timeout.0)
+            .as_secs()
+            / POLL_INTERVAL.as_secs();
+
+        debug!("Waiting for {} task {}", self.provider.name(), task_id);
+
+        for _ in 0..polls.max(1) {
+            client.sleep(POLL_INTERVAL).await;
+
+            let result: TaskResponse = self
+                .call(
+                    client,
+                    "getTaskResult",
+                    json!({ "clientKey": self.api_key, "taskId": task_id }),
+                )
+                .await?;
+
+            if result.status.as_deref() != Some("ready") {
+                continue;
+            }
+
+            let solution = result
+                .solution
+                .ok_or("Captcha solver returned no solution")?;
+
+            let mut solved = Solution::new(
+                solution.challenge.as_deref().unwrap_or(challenge),
+                &solution.validate,
+            );
+
+            if let Some(seccode) = solution.seccode {
+                solved.seccode = seccode;
+            }
+
+            return Ok(solved);
+        }
+
+        Err(format!(
+            "{} did not solve the captcha in time",
+            self.provider.name()
+        ))
+    }
+
+    async fn call<C: HttpClient>(
+        &self,
+        client: &C,
+        method: &str,
+        body: Value,
+    ) -> Result<TaskResponse, String> {
+        let failed =
+            |e: &dyn std::fmt::Display| format!("{} request failed: {}", self.provider.name(), e);
+
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .uri(format!("{}/{}", self.provider.base_url(), method))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string().into_bytes())
+            .map_err(|e| failed(&e))?;
+        let response = client.execute(request).await.map_err(|e| failed(&e))?;
+        let response: TaskResponse =
+            serde_json::from_slice(response.body()).map_err(|e| failed(&e))?;
+
+        if response.error_id != 0 {
+            return Err(format!(
+                "{} error: {}",
+                self.provider.name(),
+                response
+                    .error_description
+                    .as_deref()
+                    .unwrap_or("unknown error")
+            ));
+        }
+
+        Ok(response)
+    }
+}
+
+/// Writes a page that loads the challenge in Geetest's widget, then asks on
+/// the terminal for the code it shows once solved.
+pub fn solve_manually(gt: &str, challenge: &str) -> Result<Solution, String> {
+    let page = env::temp_dir().join(format!("hoyo-captcha-{}.html", challenge));
+    let html = include_str!("captcha.html")
+        .replace("{gt}", &json!(gt).to_string())
+        .replace("{challenge}", &json!(challenge).to_string());
+
+    fs::write(&page, html).map_err(|e| format!("Failed to write {}: {}", page.display(), e))?;
+
+    eprint!(
+        "Open file://{} in a browser, solve the captcha and paste the code it shows: ",
+        page.display()
+    );
+    io::stderr().flush().ok();
+
+    let mut validate = String::new();
+    let read = io::stdin().read_line(&mut validate);
+    let _ = fs::remove_file(&page);
+    read.map_err(|e| e.to_string())?;
+
+    match validate.trim() {
+        "" => Err("No code entered".to_string()),
+        validate => Ok(Solution::new(challenge, validate)),
+    }
+}
@@ -0,0 +1,396 @@
+use crate::{browser::Browser, daemon::Schedule};
+use serde::{de, Deserialize, Deserializer};
+use std::{
+    collections::hash_map::RandomState,
+    env,
+    hash::{BuildHasher, Hasher},
+    io::{self, IsTerminal},
+    path::PathBuf,
+    time::Duration,
+};
+
+pub enum Mode {
+    /// Run a single pass and exit (the historical behavior).
+    Once,
+    /// Run a single pass and exit with a status code reflecting the result.
+    DockerOneshot,
+    /// Run forever, sleeping between passes according to the schedule.
+    DockerLoop(Schedule),
+    /// Run a pass per AWS Lambda invocation.
+    Lambda,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable log lines only.
+    Text,
+    /// One JSON object per account and game on stdout; logs go to stderr.
+    Json,
+}
+
+pub enum Command {
+    /// Check in every configured account.
+    Run,
+    /// Print the HoYoLAB cookies found in a browser HAR capture.
+    ImportHar(PathBuf),
+    /// Check every account's cookies without checking in.
+    Validate,
+    /// Load the config and check it for mistakes, without checking in. Secrets
+    /// from `cookies_cmd`, Vault and `aws:` references are still resolved, so
+    /// their commands run and their services are asked.
+    CheckConfig,
+    /// Print this month's check-in rewards and which have been claimed.
+    Rewards,
+    /// Print every account's Real-Time Notes and notify about the meters
+    /// close to their cap.
+    Notes,
+    /// Exit non-zero unless the state file shows a recent successful run, for
+    /// Docker's `HEALTHCHECK`.
+    Health,
+    /// Print the check-in attempts recorded in the state file.
+    History,
+    /// Print each game's success and captcha rates and average check-in time
+    /// over these windows, as given and parsed.
+    Stats(Vec<(String, Duration)>),
+    /// Redeem these gift codes, or the configured ones if empty.
+    Redeem(Vec<String>),
+    /// Print the compile-time features this binary was built with.
+    Features,
+    /// Write a systemd service and timer for this config into the directory.
+    GenerateSystemd(PathBuf),
+    /// Interactively add an account to the config file.
+    AddAccount,
+    /// Copy the HoYoLAB cookies of a local browser profile into an account.
+    ImportCookies {
+        browser: Browser,
+        account: String,
+        /// Cookie database to read instead of the browser's default profile.
+        database: Option<PathBuf>,
+    },
+}
+
+pub struct Args {
+    pub command: Command,
+    pub mode: Mode,
+    pub output: OutputFormat,
+    /// -1 for `-q`, 0 by default, 1 for `-v`, 2 for `-vv`.
+    pub verbosity: i8,
+    pub dry_run: bool,
+    /// Check in even where the state file says today's reward was claimed.
+    pub force: bool,
+    /// Only retry the games whose latest attempt in the state file failed.
+    pub retry_failed: bool,
+    /// Only run these accounts (case-insensitive `Account.name`). Empty means all.
+    pub accounts: Vec<String>,
+    /// Only run accounts with any of these tags (case-insensitive). Empty means all.
+    pub tags: Vec<String>,
+    /// Only run these games (case-insensitive `Game::id` or `Game::name`). Empty means all.
+    pub games: Vec<String>,
+    /// Named entry of the config's `profiles` to apply over the top level.
+    pub profile: Option<String>,
+    /// The config file, `-` for stdin.
+    pub config: PathBuf,
+    /// Where to write the JSON Lines event stream, `-` for stdout.
+    pub events: Option<PathBuf>,
+    /// Where to write the full exchange of each failed HoYoLAB request.
+    pub debug_dump: Option<PathBuf>,
+    /// Where to write the run's results as a single JSON document.
+    pub output_file: Option<PathBuf>,
+    /// Emit GitHub Actions annotations and a job summary.
+    pub gha: bool,
+    pub no_color: bool,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        let mut mode = mode_from_env()?;
+        let mut har = None;
+        let mut cookie_db = None;
+        let mut output = OutputFormat::Text;
+        let mut verbosity = 0;
+        let mut dry_run = false;
+        let mut force = false;
+        let mut retry_failed = false;
+        let mut accounts = Vec::new();
+        let mut tags = Vec::new();
+        let mut games = Vec::new();
+        let mut profile = None;
+        let mut config = PathBuf::from("config.json");
+        let mut events = None;
+        let mut debug_dump = None;
+        let mut output_file = None;
+        let mut gha = false;
+        let mut windows = Vec::new();
+        let mut no_color = false;
+        let mut positional = Vec::new();
+        let mut args = env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+
+            match flag.as_str() {
+                "-q" | "--quiet" => verbosity = -1,
+                "-v" | "--verbose" => verbosity = (verbosity + 1).max(1),
+                "-vv" => verbosity = 2,
+                "--dry-run" => dry_run = true,
+                "--no-color" => no_color = true,
+                "--gha" => gha = true,
+                "--force" => force = true,
+                "--retry-failed" => retry_failed = true,
+                "--account" => accounts.push(value(&flag, inline, &mut args)?),
+                "--tag" => tags.push(value(&flag, inline, &mut args)?),
+                "--game" => games.push(value(&flag, inline, &mut args)?),
+                "--profile" => profile = Some(value(&flag, inline, &mut args)?),
+                "--config" => config = PathBuf::from(value(&flag, inline, &mut args)?),
+                "--events" => events = Some(PathBuf::from(value(&flag, inline, &mut args)?)),
+                "--debug-dump" => {
+                    debug_dump = Some(PathBuf::from(value(&flag, inline, &mut args)?))
+                }
+                "--docker-oneshot" => mode = Mode::DockerOneshot,
+                "--lambda" if cfg!(feature = "lambda") => mode = Mode::Lambda,
+                "--lambda" => {
+                    return Err("--lambda: this binary was built without the lambda feature".into())
+                }
+                "--docker-loop" => {
+                    let value = value(&flag, inline, &mut args)?;
+                    mode = Mode::DockerLoop(Schedule::parse(&value)?);
+                }
+                "--output" => {
+                    output = match value(&flag, inline, &mut args)?.as_str() {
+                        "text" => OutputFormat::Text,
+                        "json" => OutputFormat::Json,
+                        other => {
+                            return Err(format!(
+                                "Invalid output format \"{}\", expected \"text\" or \"json\"",
+                                other
+                            ))
+                        }
+                    }
+                }
+                "--output-file" => {
+                    output_file = Some(PathBuf::from(value(&flag, inline, &mut args)?))
+                }
+                "--window" => {
+                    let window = value(&flag, inline, &mut args)?;
+                    windows.push((window.clone(), parse_duration(&window)?));
+                }
+                "--har" => har = Some(PathBuf::from(value(&flag, inline, &mut args)?)),
+                "--cookie-db" => cookie_db = Some(PathBuf::from(value(&flag, inline, &mut args)?)),
+                _ if !arg.starts_with('-') => positional.push(arg),
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        let command = match positional
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [] => Command::Run,
+            ["validate"] => Command::Validate,
+            ["check-config"] => Command::CheckConfig,
+            ["rewards"] => Command::Rewards,
+            ["notes"] => Command::Notes,
+            ["history"] => Command::History,
+            ["stats"] if windows.is_empty() => Command::Stats(vec![
+                ("7d".to_string(), Duration::from_secs(7 * 24 * 60 * 60)),
+                ("30d".to_string(), Duration::from_secs(30 * 24 * 60 * 60)),
+            ]),
+            ["stats"] => Command::Stats(windows),
+            ["health"] => Command::Health,
+            ["redeem", codes @ ..] => {
+                Command::Redeem(codes.iter().map(|code| code.to_string()).collect())
+            }
+            ["features"] => Command::Features,
+            ["generate", "systemd"] => Command::GenerateSystemd(PathBuf::from(".")),
+            ["generate", "systemd", dir] => Command::GenerateSystemd(PathBuf::from(dir)),
+            ["add-account"] => Command::AddAccount,
+            ["import-cookies", browser] => Command::ImportCookies {
+                browser: Browser::parse(browser)?,
+                account: match accounts.as_slice() {
+                    [account] => account.clone(),
+                    _ => return Err("import-cookies requires a single --account <name>".into()),
+                },
+                database: cookie_db,
+            },
+            ["cookies", "import"] => {
+                Command::ImportHar(har.ok_or("cookies import requires --har <file>")?)
+            }
+            other => return Err(format!("Unknown command: {}", other.join(" "))),
+        };
+
+        Ok(Self {
+            command,
+            mode,
+            output,
+            verbosity: verbosity.min(2),
+            dry_run,
+            force,
+            retry_failed,
+            accounts,
+            tags,
+            games,
+            profile,
+            config,
+            events,
+            debug_dump,
+            output_file,
+            gha,
+            no_color,
+        })
+    }
+
+    /// Whether to color what goes to stdout, or to stderr with `to_stderr`.
+    /// Only terminals get colors, and not with `--no-color` or `NO_COLOR`.
+    pub fn color(&self, to_stderr: bool) -> bool {
+        let terminal = if to_stderr {
+            io::stderr().is_terminal()
+        } else {
+            io::stdout().is_terminal()
+        };
+
+        terminal && !self.no_color && env::var_os("NO_COLOR").is_none()
+    }
+}
+
+/// Whether `name` passes a case-insensitive name filter. An empty filter matches everything.
+pub fn matches_filter(filter: &[String], name: &str) -> bool {
+    filter.is_empty() || filter.iter().any(|f| f.eq_ignore_ascii_case(name))
+}
+
+fn value(
+    flag: &str,
+    inline: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, String> {
+    inline
+        .or_else(|| args.next())
+        .ok_or_else(|| format!("{} requires a value", flag))
+}
+
+/// Lets container and Lambda deployments pick a mode without overriding the
+/// entrypoint.
+fn mode_from_env() -> Result<Mode, String> {
+    match env::var("HOYO_DOCKER_MODE").as_deref() {
+        // Lambda runs the `bootstrap` binary without arguments.
+        Err(_) | Ok("")
+            if cfg!(feature = "lambda") && env::var_os("AWS_LAMBDA_RUNTIME_API").is_some() =>
+        {
+            Ok(Mode::Lambda)
+        }
+        Err(_) | Ok("") => Ok(Mode::Once),
+        Ok("oneshot") => Ok(Mode::DockerOneshot),
+        Ok("loop") => {
+            let interval = env::var("HOYO_DOCKER_INTERVAL").unwrap_or_else(|_| "24h".to_string());
+            Ok(Mode::DockerLoop(Schedule::parse(&interval)?))
+        }
+        Ok(other) => Err(format!(
+            "Invalid HOYO_DOCKER_MODE \"{}\", expected \"oneshot\" or \"loop\"",
+            other
+        )),
+    }
+}
+
+/// Parses durations such as `90s`, `30m`, `24h` or `1h30m`. A bare number is
+/// seconds. Zero is allowed, so that delays like `0s-5s` can start at once.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration \"{}\"", value);
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let amount: u64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        total = amount
+            .checked_mul(unit)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(|| format!("Duration \"{}\" is too long", value))?;
+    }
+
+    if !number.is_empty() || value.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+/// A duration written like `30m` or `1h30m` in the config file.
+#[derive(Clone, Copy)]
+pub struct HumanDuration(pub Duration);
+
+impl<'de> Deserialize<'de> for HumanDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse_duration(&value).map(Self).map_err(de::Error::custom)
+    }
+}
+
+/// A pause written as a duration like `2s`, or a range like `1s-5s` to wait
+/// a random time within.
+#[derive(Clone, Copy)]
+pub struct Delay {
+    min: Duration,
+    max: Duration,
+}
+
+impl Delay {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (min, max) = match value.split_once('-') {
+            Some((min, max)) => (parse_duration(min)?, parse_duration(max)?),
+            None => {
+                let delay = parse_duration(value)?;
+                (delay, delay)
+            }
+        };
+
+        if min > max {
+            return Err(format!("Invalid delay range \"{}\"", value));
+        }
+
+        Ok(Self { min, max })
+    }
+
+    pub fn pick(&self) -> Duration {
+        let spread = (self.max - self.min).as_millis() as u64;
+
+        if spread == 0 {
+            return self.min;
+        }
+
+        // Randomly seeded by the standard library, which is plenty to keep
+        // requests from landing at a fixed rhythm.
+        let random = RandomState::new().build_hasher().finish();
+        self.min + Duration::from_millis(random % (spread + 1))
+    }
+}
+
+impl<'de> Deserialize<'de> for Delay {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::parse(&value).map_err(de::Error::custom)
+    }
+}
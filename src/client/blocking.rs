@@ -0,0 +1,51 @@
+use super::{Error, HttpClient, Request, Response};
+use std::{
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+impl HttpClient for reqwest::blocking::Client {
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        let response = reqwest::blocking::Client::execute(self, request.try_into()?)?;
+
+        let mut builder = http::Response::builder().status(response.status());
+
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(response.headers().clone());
+        }
+
+        Ok(builder.body(response.bytes()?.to_vec())?)
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Drives the async check-in logic to completion on the current thread.
+/// With the blocking backend every future is ready on its first poll.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+
+        thread::park();
+    }
+}
@@ -0,0 +1,83 @@
+use super::{Error, HttpClient, Request, Response};
+use js_sys::{Array, Promise, Uint8Array};
+use std::time::Duration;
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, RequestInit};
+
+// Imported from the global scope rather than `Window`, so this also works in
+// workers and edge runtimes.
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = fetch)]
+    fn global_fetch(request: &web_sys::Request) -> Promise;
+
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &js_sys::Function, timeout: i32) -> JsValue;
+}
+
+/// The `fetch` API of the wasm host.
+pub struct Fetch;
+
+impl HttpClient for Fetch {
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        let (parts, body) = request.into_parts();
+
+        let headers = Headers::new().map_err(js_error)?;
+
+        for (name, value) in &parts.headers {
+            headers
+                .append(name.as_str(), value.to_str()?)
+                .map_err(js_error)?;
+        }
+
+        let init = RequestInit::new();
+        init.set_method(parts.method.as_str());
+        init.set_headers(&headers);
+
+        if !body.is_empty() {
+            init.set_body(&Uint8Array::from(body.as_slice()));
+        }
+
+        let request = web_sys::Request::new_with_str_and_init(&parts.uri.to_string(), &init)
+            .map_err(js_error)?;
+        let response: web_sys::Response = JsFuture::from(global_fetch(&request))
+            .await
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(js_error)?;
+
+        let mut builder = http::Response::builder().status(response.status());
+
+        if let Some(entries) = js_sys::try_iter(&response.headers()).map_err(js_error)? {
+            for entry in entries {
+                let entry: Array = entry.map_err(js_error)?.unchecked_into();
+
+                if let (Some(name), Some(value)) =
+                    (entry.get(0).as_string(), entry.get(1).as_string())
+                {
+                    builder = builder.header(name, value);
+                }
+            }
+        }
+
+        let body = JsFuture::from(response.array_buffer().map_err(js_error)?)
+            .await
+            .map_err(js_error)?;
+
+        Ok(builder.body(Uint8Array::new(&body).to_vec())?)
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let timeout = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+        let promise = Promise::new(&mut |resolve, _| {
+            set_timeout(&resolve, timeout);
+        });
+
+        let _ = JsFuture::from(promise).await;
+    }
+}
+
+fn js_error(value: JsValue) -> Error {
+    format!("{:?}", value).into()
+}
@@ -0,0 +1,74 @@
+//! A client with Chrome's TLS and HTTP/2 fingerprint, for when HoYoLAB's WAF
+//! answers rustls' handshake with captchas or rejections.
+
+use super::{Error, HttpClient, Request, Response};
+use std::time::Duration;
+
+#[cfg(feature = "impersonate")]
+pub struct Impersonate {
+    client: wreq::Client,
+    /// wreq is async only, so each request runs on this runtime to completion.
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "impersonate")]
+impl Impersonate {
+    pub fn new() -> Result<Self, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let client = {
+            let _runtime = runtime.enter();
+            wreq::Client::builder()
+                .emulation(wreq_util::Profile::Chrome149)
+                .build()?
+        };
+
+        Ok(Self { client, runtime })
+    }
+
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        let response = self.client.execute(request.into()).await?;
+
+        let mut builder = http::Response::builder().status(response.status());
+
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(response.headers().clone());
+        }
+
+        Ok(builder.body(response.bytes().await?.to_vec())?)
+    }
+}
+
+#[cfg(feature = "impersonate")]
+impl HttpClient for Impersonate {
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        self.runtime.block_on(self.send(request))
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Can never be built without the impersonate feature.
+#[cfg(not(feature = "impersonate"))]
+pub enum Impersonate {}
+
+#[cfg(not(feature = "impersonate"))]
+impl Impersonate {
+    pub fn new() -> Result<Self, Error> {
+        Err("this binary was built without the impersonate feature".into())
+    }
+}
+
+#[cfg(not(feature = "impersonate"))]
+impl HttpClient for Impersonate {
+    async fn execute(&self, _request: Request) -> Result<Response, Error> {
+        match *self {}
+    }
+
+    async fn sleep(&self, _duration: Duration) {
+        match *self {}
+    }
+}
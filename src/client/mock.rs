@@ -0,0 +1,103 @@
+use super::{copy_request, Error, HttpClient, Request, Response};
+use std::{cell::RefCell, collections::VecDeque, time::Duration};
+
+/// Responses to give for a path suffix, in order. The last one repeats.
+type Route = (String, VecDeque<Result<Response, String>>);
+
+/// Answers requests with scripted responses instead of going over the
+/// network, and records them for inspection.
+#[derive(Default)]
+pub struct MockClient {
+    routes: RefCell<Vec<Route>>,
+    requests: RefCell<Vec<Request>>,
+    slept: RefCell<Vec<Duration>>,
+}
+
+impl MockClient {
+    /// Answers requests to paths ending in `path` with `body`, after any
+    /// responses scripted for it earlier.
+    pub fn on(self, path: &str, status: u16, body: &str) -> Self {
+        let response = http::Response::builder()
+            .status(status)
+            .body(body.as_bytes().to_vec())
+            .map_err(|e| e.to_string());
+
+        self.push(path, response)
+    }
+
+    /// Like `on`, for responses that need more than a status and body.
+    pub fn respond(self, path: &str, response: Response) -> Self {
+        self.push(path, Ok(response))
+    }
+
+    /// Fails requests to paths ending in `path` as if the connection broke.
+    pub fn fail(self, path: &str, error: &str) -> Self {
+        self.push(path, Err(error.to_string()))
+    }
+
+    fn push(self, path: &str, response: Result<Response, String>) -> Self {
+        {
+            let mut routes = self.routes.borrow_mut();
+
+            match routes.iter_mut().find(|(route, _)| route == path) {
+                Some((_, responses)) => responses.push_back(response),
+                None => routes.push((path.to_string(), VecDeque::from([response]))),
+            }
+        }
+
+        self
+    }
+
+    /// The requests made to paths ending in `path`, oldest first.
+    pub fn requests(&self, path: &str) -> Vec<Request> {
+        self.requests
+            .borrow()
+            .iter()
+            .filter(|request| request.uri().path().ends_with(path))
+            .map(copy_request)
+            .collect()
+    }
+
+    /// Every wait asked for, in order.
+    pub fn slept(&self) -> Vec<Duration> {
+        self.slept.borrow().clone()
+    }
+}
+
+impl HttpClient for MockClient {
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        let path = request.uri().path().to_string();
+        self.requests.borrow_mut().push(request);
+
+        let mut routes = self.routes.borrow_mut();
+        let Some((_, responses)) = routes
+            .iter_mut()
+            .filter(|(route, _)| path.ends_with(route.as_str()))
+            .max_by_key(|(route, _)| route.len())
+        else {
+            return Ok(http::Response::builder()
+                .status(404)
+                .body(Vec::new())
+                .expect("Valid response"));
+        };
+
+        let response = match responses.len() {
+            1 => match &responses[0] {
+                Ok(response) => {
+                    let mut copy = http::Response::new(response.body().clone());
+                    *copy.status_mut() = response.status();
+                    *copy.headers_mut() = response.headers().clone();
+                    Ok(copy)
+                }
+                Err(e) => Err(e.clone()),
+            },
+            _ => responses.pop_front().expect("Scripted response"),
+        };
+
+        response.map_err(Into::into)
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.slept.borrow_mut().push(duration);
+    }
+}
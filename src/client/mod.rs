@@ -0,0 +1,70 @@
+//! The HTTP layer the check-in logic runs on. Backends are selected with
+//! features, so the same sign logic serves the blocking command-line binary,
+//! async runtimes and wasm hosts that only offer `fetch`.
+
+#[cfg(feature = "reqwest-blocking")]
+mod blocking;
+#[cfg(all(feature = "fetch", target_arch = "wasm32"))]
+mod fetch;
+mod impersonate;
+#[cfg(test)]
+pub mod mock;
+#[cfg(feature = "reqwest-async")]
+mod reqwest_async;
+
+#[cfg(feature = "reqwest-blocking")]
+pub use blocking::block_on;
+#[cfg(all(feature = "fetch", target_arch = "wasm32"))]
+pub use fetch::Fetch;
+pub use impersonate::Impersonate;
+
+use std::time::Duration;
+
+pub type Request = http::Request<Vec<u8>>;
+pub type Response = http::Response<Vec<u8>>;
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// `http::Request` is not `Clone`, as bodies in general cannot be copied.
+pub fn copy_request(request: &Request) -> Request {
+    let mut copy = Request::new(request.body().clone());
+    *copy.method_mut() = request.method().clone();
+    *copy.uri_mut() = request.uri().clone();
+    *copy.headers_mut() = request.headers().clone();
+    copy
+}
+
+// Without `Send` bounds, as the futures of the `fetch` backend are not.
+#[allow(async_fn_in_trait)]
+pub trait HttpClient {
+    /// Sends a request. Only transport failures are errors; any HTTP status
+    /// is returned as a response.
+    async fn execute(&self, request: Request) -> Result<Response, Error>;
+
+    /// Waits without holding up the host, for polling and backoff.
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The client HoYoLAB requests go through, see the config's `impersonate`.
+#[cfg(feature = "reqwest-blocking")]
+#[derive(Clone, Copy)]
+pub enum Backend<'a> {
+    Reqwest(&'a reqwest::blocking::Client),
+    Impersonate(&'a Impersonate),
+}
+
+#[cfg(feature = "reqwest-blocking")]
+impl HttpClient for Backend<'_> {
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        match self {
+            Self::Reqwest(client) => HttpClient::execute(*client, request).await,
+            Self::Impersonate(client) => client.execute(request).await,
+        }
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        match self {
+            Self::Reqwest(client) => HttpClient::sleep(*client, duration).await,
+            Self::Impersonate(client) => client.sleep(duration).await,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use super::{Error, HttpClient, Request, Response};
+use std::time::Duration;
+
+/// Needs to run inside a Tokio runtime, as reqwest's async client does.
+impl HttpClient for reqwest::Client {
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        let response = reqwest::Client::execute(self, request.try_into()?).await?;
+
+        let mut builder = http::Response::builder().status(response.status());
+
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(response.headers().clone());
+        }
+
+        Ok(builder.body(response.bytes().await?.to_vec())?)
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
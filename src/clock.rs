@@ -0,0 +1,73 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// HoYoLAB rolls over daily check-ins at midnight in UTC+8.
+pub const SERVER_UTC_OFFSET: u64 = 8 * 60 * 60;
+
+pub const DAY: u64 = 24 * 60 * 60;
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// For timing what takes less than a second.
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// How long to wait from `now` until `offset` past the next server reset.
+/// If `now` is still within `offset` of today's reset, waits for today's.
+pub fn until_after_reset(now: u64, offset: Duration) -> Duration {
+    let server_now = now + SERVER_UTC_OFFSET;
+    let today_target = server_now - server_now % DAY + offset.as_secs() % DAY;
+
+    let target = if today_target > server_now {
+        today_target
+    } else {
+        today_target + DAY
+    };
+
+    Duration::from_secs(target - server_now)
+}
+
+/// Formats a Unix timestamp as an RFC 3339 UTC date-time.
+pub fn rfc3339(unix: u64) -> String {
+    let (year, month, day) = civil_from_days(unix / DAY);
+    let seconds = unix % DAY;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// The HoYoLAB server day (UTC+8) a Unix timestamp falls on, as `YYYY-MM-DD`.
+/// This is the day a check-in at that moment counts towards.
+pub fn server_date(unix: u64) -> String {
+    let (year, month, day) = civil_from_days((unix + SERVER_UTC_OFFSET) / DAY);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Converts days since the Unix epoch to a (year, month, day) civil date.
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
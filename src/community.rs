@@ -0,0 +1,190 @@
+use crate::{
+    client::HttpClient, ds, games::Genshin, retcode, CheckinError, HoyolabCheckin, SignResponse,
+};
+use http::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{fmt, time::Duration};
+use tracing::{debug, info, warn};
+
+const API: &str = "https://bbs-api-os.hoyolab.com/community";
+
+/// How many posts each daily mission asks for.
+const READ_POSTS: usize = 3;
+const LIKE_POSTS: usize = 5;
+const SHARE_POSTS: usize = 1;
+
+/// Between actions, so the missions are not done faster than anyone could.
+const PAUSE: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize)]
+struct NewsData {
+    #[serde(default)]
+    list: Vec<NewsItem>,
+}
+
+#[derive(Deserialize)]
+struct NewsItem {
+    post: Post,
+}
+
+#[derive(Deserialize)]
+struct Post {
+    post_id: String,
+}
+
+/// What the community missions got done.
+#[derive(Default)]
+pub struct CommunitySummary {
+    pub signed_in: bool,
+    pub read: usize,
+    pub liked: usize,
+    pub shared: usize,
+}
+
+impl fmt::Display for CommunitySummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "💬 HoYoLAB community: {}read {}, liked {} and shared {} posts",
+            if self.signed_in { "signed in, " } else { "" },
+            self.read,
+            self.liked,
+            self.shared
+        )
+    }
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// Does the HoYoLAB community's daily missions: signing in, reading,
+    /// liking and sharing posts. Likes are taken back right away. A failed
+    /// mission is logged and the others carried on with.
+    pub async fn community_tasks(&self) -> Result<CommunitySummary, CheckinError> {
+        let mut summary = CommunitySummary::default();
+
+        match self
+            .community(
+                Method::POST,
+                "apihub/api/signIn",
+                &[],
+                json!({ "gids": "2" }),
+            )
+            .await
+        {
+            Ok(_)
+            | Err(CheckinError::Api {
+                retcode: retcode::COMMUNITY_SIGNED,
+                ..
+            }) => summary.signed_in = true,
+            Err(e @ CheckinError::CookieExpired { .. }) => return Err(e),
+            Err(e) => warn!("Failed to sign in to the community: {}", e),
+        }
+
+        let posts: Vec<String> = self
+            .community(
+                Method::GET,
+                "post/wapi/getNewsList",
+                &[("gids", "2"), ("page_size", "20"), ("type", "1")],
+                Value::Null,
+            )
+            .await
+            .and_then(|data| {
+                serde_json::from_value::<NewsData>(data)
+                    .map_err(|e| CheckinError::UnexpectedResponse(e.to_string()))
+            })?
+            .list
+            .into_iter()
+            .map(|item| item.post.post_id)
+            .collect();
+
+        debug!("Found {} posts for community missions", posts.len());
+
+        for post in posts.iter().take(READ_POSTS) {
+            self.client.sleep(PAUSE).await;
+
+            match self
+                .community(
+                    Method::GET,
+                    "post/wapi/getPostFull",
+                    &[("post_id", post), ("read", "1")],
+                    Value::Null,
+                )
+                .await
+            {
+                Ok(_) => summary.read += 1,
+                Err(e) => warn!("Failed to read post {}: {}", post, e),
+            }
+        }
+
+        for post in posts.iter().take(LIKE_POSTS) {
+            self.client.sleep(PAUSE).await;
+
+            if let Err(e) = self.upvote(post, false).await {
+                warn!("Failed to like post {}: {}", post, e);
+                continue;
+            }
+
+            summary.liked += 1;
+
+            if let Err(e) = self.upvote(post, true).await {
+                warn!("Failed to take back like of post {}: {}", post, e);
+            }
+        }
+
+        for post in posts.iter().take(SHARE_POSTS) {
+            self.client.sleep(PAUSE).await;
+
+            match self
+                .community(
+                    Method::GET,
+                    "apihub/api/getShareConf",
+                    &[("entity_id", post), ("entity_type", "1")],
+                    Value::Null,
+                )
+                .await
+            {
+                Ok(_) => summary.shared += 1,
+                Err(e) => warn!("Failed to share post {}: {}", post, e),
+            }
+        }
+
+        info!("{}", summary);
+        Ok(summary)
+    }
+
+    async fn upvote(&self, post: &str, cancel: bool) -> Result<Value, CheckinError> {
+        self.community(
+            Method::POST,
+            "apihub/sapi/upvotePost",
+            &[],
+            json!({ "post_id": post, "is_cancel": cancel }),
+        )
+        .await
+    }
+
+    /// Calls a community endpoint, returning the response's data.
+    async fn community(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Value,
+    ) -> Result<Value, CheckinError> {
+        let body = match body {
+            Value::Null => Vec::new(),
+            body => body.to_string().into_bytes(),
+        };
+        let mut request =
+            self.request(&Genshin, method, &format!("{}/{}", API, path), query, body)?;
+        ds::sign(request.headers_mut(), &self.account.name, self.lang());
+
+        let response: SignResponse<Value> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        Ok(response.data.unwrap_or_default())
+    }
+}
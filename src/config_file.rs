@@ -0,0 +1,129 @@
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Whether the config path stands for stdin, which can be read once but not
+/// written back to.
+pub fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// The `*.json` files in the `config.d` directory next to the config file,
+/// in name order. Each holds one account, merged into `accounts` on load.
+pub fn account_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if is_stdin(path) {
+        return Ok(Vec::new());
+    }
+
+    let entries = match fs::read_dir(path.with_file_name("config.d")) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut files = Vec::new();
+
+    for entry in entries {
+        let file = entry?.path();
+
+        if file
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            files.push(file);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Replaces an account's cookies in its `config.d` file, or in the config
+/// file if it has none.
+pub fn save_cookies(
+    path: &Path,
+    profile: Option<&str>,
+    account: &str,
+    cookies: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let files = account_files(path).map_err(|e| format!("Failed to list config.d: {}", e))?;
+
+    for file in files {
+        let failed =
+            |e: &dyn std::fmt::Display| format!("Failed to update {}: {}", file.display(), e);
+        let data = fs::read_to_string(&file).map_err(|e| failed(&e))?;
+        let mut entry: Value = serde_json::from_str(&data).map_err(|e| failed(&e))?;
+
+        if entry["name"].as_str() == Some(account) {
+            entry["cookies"] = json!(cookies);
+            return write(&file, &entry).map_err(|e| failed(&e));
+        }
+    }
+
+    edit_accounts(path, profile, |accounts| {
+        let entry = accounts
+            .iter_mut()
+            .find(|entry| entry["name"].as_str() == Some(account))
+            .ok_or_else(|| format!("account {} not found", account))?;
+
+        entry["cookies"] = json!(cookies);
+        Ok(())
+    })
+}
+
+/// Appends an account to the config file.
+pub fn add_account(
+    path: &Path,
+    profile: Option<&str>,
+    account: &str,
+    cookies: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    edit_accounts(path, profile, |accounts| {
+        accounts.push(json!({ "name": account, "cookies": cookies }));
+        Ok(())
+    })
+}
+
+/// Edits the accounts list in place, keeping the rest of the file as it is.
+/// Accounts overridden by the active profile live in the profile.
+fn edit_accounts(
+    path: &Path,
+    profile: Option<&str>,
+    edit: impl FnOnce(&mut Vec<Value>) -> Result<(), String>,
+) -> Result<(), String> {
+    if is_stdin(path) {
+        return Err("Cannot update a config read from stdin".to_string());
+    }
+
+    let failed = |e: &dyn std::fmt::Display| format!("Failed to update {}: {}", path.display(), e);
+
+    let data = fs::read_to_string(path).map_err(|e| failed(&e))?;
+    let mut config: Value = serde_json::from_str(&data).map_err(|e| failed(&e))?;
+
+    let accounts = match profile {
+        Some(profile) if config["profiles"][profile]["accounts"].is_array() => {
+            &mut config["profiles"][profile]["accounts"]
+        }
+        _ => &mut config["accounts"],
+    };
+
+    edit(
+        accounts
+            .as_array_mut()
+            .ok_or_else(|| failed(&"accounts is not a list"))?,
+    )
+    .map_err(|e| failed(&e))?;
+
+    write(path, &config).map_err(|e| failed(&e))
+}
+
+/// Replaces the file through a temporary one, so it is never left half written.
+fn write(path: &Path, value: &Value) -> io::Result<()> {
+    let mut temp = path.to_path_buf().into_os_string();
+    temp.push(".tmp");
+
+    fs::write(&temp, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(&temp, path)
+}
@@ -0,0 +1,184 @@
+use crate::clock;
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Domains whose cookies are needed to talk to the check-in APIs.
+const COOKIE_DOMAINS: &[&str] = &["hoyolab.com", "hoyoverse.com"];
+
+#[derive(Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Deserialize)]
+struct HarLog {
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Deserialize)]
+struct HarEntry {
+    request: HarRequest,
+}
+
+#[derive(Deserialize)]
+struct HarRequest {
+    url: String,
+    #[serde(default)]
+    cookies: Vec<HarPair>,
+    #[serde(default)]
+    headers: Vec<HarPair>,
+}
+
+#[derive(Deserialize)]
+struct HarPair {
+    name: String,
+    value: String,
+}
+
+pub fn is_hoyolab_host(host: &str) -> bool {
+    COOKIE_DOMAINS
+        .iter()
+        .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+}
+
+/// Collects the cookies sent to HoYoLAB in a browser HAR capture. Later
+/// requests win, so the freshest value of a rotated token is kept.
+pub fn from_har(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let har: Har = serde_json::from_str(&data)
+        .map_err(|e| format!("Invalid HAR file {}: {}", path.display(), e))?;
+
+    let mut cookies = BTreeMap::new();
+
+    for entry in har.log.entries {
+        let request = entry.request;
+        let is_hoyolab = Url::parse(&request.url)
+            .ok()
+            .and_then(|url| url.host_str().map(is_hoyolab_host))
+            .unwrap_or(false);
+
+        if !is_hoyolab {
+            continue;
+        }
+
+        if request.cookies.is_empty() {
+            // Some browsers only record the raw Cookie header.
+            for header in &request.headers {
+                if header.name.eq_ignore_ascii_case("cookie") {
+                    cookies.extend(parse_cookie_header(&header.value));
+                }
+            }
+        } else {
+            cookies.extend(
+                request
+                    .cookies
+                    .into_iter()
+                    .map(|cookie| (cookie.name, cookie.value)),
+            );
+        }
+    }
+
+    if cookies.is_empty() {
+        return Err(format!(
+            "No HoYoLAB cookies found in {}, make sure the capture includes a request to hoyolab.com",
+            path.display()
+        ));
+    }
+
+    Ok(cookies)
+}
+
+/// Collects the HoYoLAB cookies of a Netscape `cookies.txt` export, skipping
+/// expired ones. Later lines win.
+pub fn from_cookies_txt(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let now = clock::unix_now();
+
+    let cookies: BTreeMap<String, String> = data
+        .lines()
+        .filter_map(|line| {
+            // HttpOnly cookies, such as ltoken_v2, are written as comments.
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+
+            if line.starts_with('#') {
+                return None;
+            }
+
+            match line.trim_end_matches('\r').split('\t').collect::<Vec<_>>()[..] {
+                [domain, _, _, _, expires, name, value] => {
+                    let expired = expires
+                        .parse::<u64>()
+                        .is_ok_and(|expires| expires != 0 && expires < now);
+
+                    (is_hoyolab_host(domain.trim_start_matches('.')) && !expired)
+                        .then(|| (name.to_string(), value.to_string()))
+                }
+                _ => None,
+            }
+        })
+        .collect();
+
+    if cookies.is_empty() {
+        return Err(format!(
+            "No HoYoLAB cookies found in {}, export them while logged in to hoyolab.com",
+            path.display()
+        ));
+    }
+
+    Ok(cookies)
+}
+
+/// Runs `command` through the shell, e.g. `op read op://vault/hoyolab/alt1`,
+/// and takes the cookies from the `Cookie` header it prints. Its stderr is
+/// passed through, so password managers can still prompt to be unlocked.
+pub fn from_command(command: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    let output = shell
+        .arg(command)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Failed to run `{}`: {}", command, e))?;
+
+    if !output.status.success() {
+        return Err(format!("`{}` exited with {}", command, output.status));
+    }
+
+    let cookies: BTreeMap<String, String> =
+        parse_cookie_header(String::from_utf8_lossy(&output.stdout).trim()).collect();
+
+    if cookies.is_empty() {
+        return Err(format!(
+            "`{}` printed no cookies, expected a Cookie header (ltuid_v2=...; ltoken_v2=...)",
+            command
+        ));
+    }
+
+    Ok(cookies)
+}
+
+/// Splits a `Cookie` header value (`a=1; b=2`) into name/value pairs.
+pub fn parse_cookie_header(header: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    header.split(';').filter_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        let name = name.trim();
+
+        (!name.is_empty()).then(|| (name.to_string(), value.trim().to_string()))
+    })
+}
@@ -0,0 +1,288 @@
+use crate::{
+    cli::{parse_duration, HumanDuration},
+    clock, config_file,
+    server::{Control, Trigger},
+    systemd,
+};
+use serde::Deserialize;
+use signal_hook::{
+    consts::{SIGHUP, TERM_SIGNALS},
+    flag,
+};
+use std::{
+    fs, io, iter,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use tracing::info;
+
+#[derive(Deserialize)]
+pub struct DaemonConfig {
+    /// Failed check-ins are only alerted on once the next server reset is
+    /// closer than this. Earlier failures are retried quietly instead.
+    pub grace_window: Option<HumanDuration>,
+    /// How long to wait before retrying a failed run.
+    #[serde(default = "default_retry_interval")]
+    pub retry_interval: HumanDuration,
+    /// Retry a failed run up to this many times within the same server day.
+    /// With only `grace_window` set, failed runs are retried until the
+    /// server reset.
+    pub retries: Option<u32>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            grace_window: None,
+            retry_interval: default_retry_interval(),
+            retries: None,
+        }
+    }
+}
+
+fn default_retry_interval() -> HumanDuration {
+    HumanDuration(Duration::from_secs(60 * 60))
+}
+
+/// How `run_loop` retries failed runs.
+#[derive(Clone, Copy)]
+pub struct Retry {
+    pub interval: Duration,
+    /// Retries per failed scheduled run. Unlimited if `None`.
+    pub limit: Option<u32>,
+}
+
+pub enum Schedule {
+    /// Run every fixed interval, starting immediately.
+    Interval(Duration),
+    /// Run immediately, then daily at the given offset after the HoYoLAB
+    /// server reset (midnight UTC+8).
+    AfterReset(Duration),
+}
+
+impl Schedule {
+    /// Accepts an interval such as `24h`, or `reset` / `reset+15m` to run
+    /// shortly after the daily server reset.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.strip_prefix("reset") {
+            Some("") => Ok(Self::AfterReset(Duration::ZERO)),
+            Some(offset) => match offset.strip_prefix('+') {
+                Some(offset) => Ok(Self::AfterReset(parse_duration(offset)?)),
+                None => Err(format!("Invalid schedule \"{}\"", value)),
+            },
+            None => match parse_duration(value)? {
+                Duration::ZERO => Err(format!("Invalid schedule \"{}\"", value)),
+                interval => Ok(Self::Interval(interval)),
+            },
+        }
+    }
+
+    /// Usual time between two scheduled runs.
+    pub fn period(&self) -> Duration {
+        match self {
+            Self::Interval(interval) => *interval,
+            Self::AfterReset(_) => Duration::from_secs(clock::DAY),
+        }
+    }
+
+    fn next_delay(&self) -> Duration {
+        match self {
+            Self::Interval(interval) => *interval,
+            Self::AfterReset(offset) => clock::until_after_reset(clock::unix_now(), *offset),
+        }
+    }
+}
+
+/// Tracks SIGINT/SIGTERM. When running as PID 1 in a container the kernel
+/// ignores these unless a handler is installed, which would make
+/// `docker stop` wait for its kill timeout.
+#[derive(Clone)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+}
+
+impl Shutdown {
+    pub fn install() -> io::Result<Self> {
+        let requested = Arc::new(AtomicBool::new(false));
+
+        for signal in TERM_SIGNALS {
+            // A second signal while we are already shutting down exits immediately.
+            flag::register_conditional_shutdown(*signal, 1, Arc::clone(&requested))?;
+            flag::register(*signal, Arc::clone(&requested))?;
+        }
+
+        Ok(Self { requested })
+    }
+
+    pub fn requested(&self) -> bool {
+        self.requested.load(Ordering::Relaxed)
+    }
+
+    /// Sleeps until `deadline`, waking early if a shutdown is requested.
+    /// `tick` is called periodically while waiting and ends the sleep early
+    /// by returning `true`. Returns `false` if a shutdown interrupted it.
+    pub fn sleep_until(&self, deadline: Instant, mut tick: impl FnMut() -> bool) -> bool {
+        while !self.requested() {
+            let now = Instant::now();
+
+            if tick() || now >= deadline {
+                return true;
+            }
+
+            thread::sleep((deadline - now).min(Duration::from_millis(250)));
+        }
+
+        false
+    }
+}
+
+/// Notices when the config should be reloaded: on SIGHUP, or when the
+/// config file or any file in `config.d` is changed, added or removed.
+pub struct ConfigWatch {
+    path: PathBuf,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    hangup: Arc<AtomicBool>,
+}
+
+impl ConfigWatch {
+    pub fn install(path: &Path) -> io::Result<Self> {
+        let hangup = Arc::new(AtomicBool::new(false));
+        flag::register(SIGHUP, Arc::clone(&hangup))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            files: modified(path),
+            hangup,
+        })
+    }
+
+    fn changed(&mut self) -> bool {
+        let files = modified(&self.path);
+        let changed = files != self.files;
+        self.files = files;
+
+        self.hangup.swap(false, Ordering::Relaxed) || changed
+    }
+}
+
+fn modified(path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    iter::once(path.to_path_buf())
+        .chain(config_file::account_files(path).unwrap_or_default())
+        .map(|file| {
+            let modified = fs::metadata(&file).and_then(|m| m.modified()).ok();
+            (file, modified)
+        })
+        .collect()
+}
+
+/// Why `run_loop` calls its pass.
+pub enum Wake {
+    /// A scheduled run. `last` if its failure would not be retried.
+    Schedule { last: bool },
+    /// A retry of a failed run. `last` if no more will follow.
+    Retry { last: bool },
+    /// A run requested over the API.
+    Request(Trigger),
+    /// The config changed and should be reloaded, without running.
+    Reload,
+}
+
+/// Runs `pass` until a shutdown signal arrives, waiting between passes as
+/// dictated by `schedule`. `pass` returns whether the run succeeded; failed
+/// runs are retried as `retry` allows, as long as that is before both the
+/// next scheduled run and the server reset. Runs requested through `control`
+/// and config reloads noticed by `watch` happen in between, without moving
+/// the schedule.
+pub fn run_loop(
+    systemd: &systemd::Notifier,
+    shutdown: &Shutdown,
+    schedule: &Schedule,
+    retry: Option<Retry>,
+    control: &Control,
+    mut watch: Option<ConfigWatch>,
+    mut pass: impl FnMut(Wake) -> bool,
+) {
+    systemd.notify("READY=1");
+
+    let mut retries = 0;
+
+    'schedule: while !shutdown.requested() {
+        systemd.status("Running check-in");
+        systemd.keepalive();
+
+        // Decided up front, so the pass knows whether to hold back alerts.
+        let until_reset = clock::until_after_reset(clock::unix_now(), Duration::ZERO);
+        let retry_follows = retry.is_some_and(|retry| {
+            retry.limit.is_none_or(|limit| retries < limit)
+                && retry.interval < schedule.next_delay().min(until_reset)
+        });
+        let last = !retry_follows;
+        let success = pass(match retries {
+            0 => Wake::Schedule { last },
+            _ => Wake::Retry { last },
+        });
+
+        if shutdown.requested() {
+            break;
+        }
+
+        let next_run = schedule.next_delay();
+        let delay = match retry {
+            Some(retry) if !success && retry_follows => {
+                retries += 1;
+                info!(
+                    "Retrying failed check-ins in {} seconds",
+                    retry.interval.as_secs()
+                );
+                retry.interval
+            }
+            _ => {
+                retries = 0;
+                info!("Next check-in in {} seconds", next_run.as_secs());
+                next_run
+            }
+        };
+        systemd.status(&format!(
+            "Last check-in {}, next in {} seconds",
+            if success { "succeeded" } else { "failed" },
+            delay.as_secs()
+        ));
+
+        let deadline = Instant::now() + delay;
+
+        loop {
+            let mut reload = false;
+            let waited = shutdown.sleep_until(deadline, || {
+                systemd.keepalive();
+                reload = watch.as_mut().is_some_and(ConfigWatch::changed);
+                reload || control.pending()
+            });
+
+            if !waited {
+                break 'schedule;
+            }
+
+            if reload {
+                info!("Reloading the config");
+                pass(Wake::Reload);
+                continue;
+            }
+
+            match control.take() {
+                Some(trigger) => {
+                    info!("Running check-in requested over the API");
+                    pass(Wake::Request(trigger));
+                }
+                None => break,
+            }
+        }
+    }
+
+    systemd.notify("STOPPING=1");
+    info!("Shutdown requested, exiting");
+}
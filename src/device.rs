@@ -0,0 +1,110 @@
+use crate::{client::HttpClient, clock, CheckinError, HoyolabCheckin, SignResponse, GAMES};
+use http::{HeaderMap, HeaderValue, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io;
+
+/// Registers a device fingerprint with HoYoverse's anti-bot service.
+const FP_URL: &str = "https://sg-public-data-api.hoyoverse.com/device-fp/api/getFp";
+
+/// The browser the fingerprint is registered for, as its fingerprinting
+/// script would describe it.
+const EXT_FIELDS: &str = r#"{"userAgent":"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Safari/537.36","browserScreenSize":2073600,"maxTouchPoints":0,"isTouchSupported":false,"browserLanguage":"en-US","browserPlat":"Win32","browserTimeZone":"UTC","webGlRender":"ANGLE (NVIDIA GeForce GTX 1060 Direct3D11 vs_5_0 ps_5_0)","webGlVendor":"Google Inc. (NVIDIA)","numOfPlugins":5,"listOfPlugins":"PDF Viewer,Chrome PDF Viewer,Chromium PDF Viewer,Microsoft Edge PDF Viewer,WebKit built-in PDF","screenRatio":1,"deviceMemory":"8","hardwareConcurrency":"8","cpuClass":"unknown","ifNotTrack":"unknown","ifAdBlock":0,"hasLiedResolution":1,"hasLiedOs":0,"hasLiedBrowser":0}"#;
+
+/// The device an account's requests claim to come from. Kept in the state
+/// file so the account looks like the same browser every day.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Device {
+    pub id: String,
+    pub fp: String,
+}
+
+#[derive(Deserialize)]
+struct FpData {
+    device_fp: String,
+    code: i32,
+    msg: Option<String>,
+}
+
+impl Device {
+    /// A new random device, with a placeholder fingerprint until one is
+    /// registered.
+    pub fn generate() -> io::Result<Self> {
+        let id = random_hex(16)?;
+        let fp = random_hex(7)?;
+
+        Ok(Self {
+            id: format!(
+                "{}-{}-4{}-a{}-{}",
+                &id[..8],
+                &id[8..12],
+                &id[13..16],
+                &id[17..20],
+                &id[20..]
+            ),
+            fp: fp[..13].to_string(),
+        })
+    }
+
+    /// Whether the id has the UUID shape `generate` gives, as state files
+    /// can be edited by hand. Invalid devices are replaced.
+    pub fn is_valid(&self) -> bool {
+        let id = self.id.replace('-', "");
+        id.len() == 32 && id.bytes().all(|byte| byte.is_ascii_hexdigit())
+    }
+
+    pub fn headers(&self, headers: &mut HeaderMap) {
+        for (name, value) in [("x-rpc-device_id", &self.id), ("x-rpc-device_fp", &self.fp)] {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.insert(name, value);
+            }
+        }
+    }
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// Registers the device and returns the fingerprint HoYoverse assigned it.
+    pub async fn device_fp(&self, device: &Device) -> Result<String, CheckinError> {
+        let id = device.id.replace('-', "");
+        let body = json!({
+            "device_id": id.get(..16).unwrap_or(&id),
+            "seed_id": device.id,
+            "seed_time": (clock::unix_now() * 1000).to_string(),
+            "platform": "4",
+            "device_fp": device.fp,
+            "app_name": "bbs_oversea",
+            "ext_fields": EXT_FIELDS,
+        });
+        let request = self.request(
+            GAMES[0],
+            Method::POST,
+            FP_URL,
+            &[],
+            body.to_string().into_bytes(),
+        )?;
+        let response: SignResponse<FpData> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        match response.data {
+            Some(data) if data.code == 200 => Ok(data.device_fp),
+            Some(data) => Err(CheckinError::Api {
+                retcode: data.code,
+                message: data.msg,
+            }),
+            None => Err(CheckinError::UnexpectedResponse(
+                "No device fingerprint".to_string(),
+            )),
+        }
+    }
+}
+
+fn random_hex(bytes: usize) -> io::Result<String> {
+    let mut buffer = vec![0; bytes];
+    getrandom::getrandom(&mut buffer).map_err(io::Error::other)?;
+
+    Ok(hex::encode(buffer))
+}
@@ -0,0 +1,123 @@
+//! `discover_act_ids`: the current check-in act_ids, as linked from each
+//! game's HoYoLAB tools, so a rotated act_id is picked up without a release
+//! or a `games` override.
+
+use crate::{
+    client::{self, HttpClient, Request},
+    games::GAMES,
+};
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+
+/// The tools HoYoLAB lists for a game, check-in page included.
+const URL_TOOLS: &str =
+    "https://bbs-api-os.hoyolab.com/community/painter/wapi/circle/channel/guide/material";
+
+/// The check-in page's path, by game id.
+const PAGES: &[(&str, &str)] = &[
+    ("genshin", "/ys/event/signin-sea"),
+    ("hsr", "/event/signin/hkrpg/"),
+    ("zzz", "/event/signin/zzz/"),
+];
+
+/// A game's check-in as currently linked from HoYoLAB.
+pub struct Discovered {
+    pub act_id: String,
+    pub url_page: String,
+}
+
+/// Looks up the act_id of every game. Games it fails for are left out, to
+/// keep their configured or built-in act_id.
+pub async fn discover<C: HttpClient>(
+    client: &C,
+    base_url: Option<&str>,
+) -> HashMap<&'static str, Discovered> {
+    let mut discovered = HashMap::new();
+
+    for game in GAMES {
+        let Some((_, path)) = PAGES.iter().find(|(id, _)| *id == game.id()) else {
+            continue;
+        };
+
+        match tools(client, base_url, game.hoyolab_id()).await {
+            Ok(tools) => match find(&tools, path) {
+                Some(found) => {
+                    info!("Discovered act_id {} for {}", found.act_id, game.name());
+                    discovered.insert(game.id(), found);
+                }
+                None => warn!(
+                    "No check-in page among HoYoLAB's tools for {}, using the configured act_id",
+                    game.name()
+                ),
+            },
+            Err(e) => warn!(
+                "Failed to discover the act_id for {}, using the configured one: {}",
+                game.name(),
+                e
+            ),
+        }
+    }
+
+    discovered
+}
+
+async fn tools<C: HttpClient>(
+    client: &C,
+    base_url: Option<&str>,
+    forum: u32,
+) -> Result<Value, client::Error> {
+    let url = match base_url {
+        Some(base) => format!(
+            "{}{}",
+            base.trim_end_matches('/'),
+            Url::parse(URL_TOOLS)?.path()
+        ),
+        None => URL_TOOLS.to_string(),
+    };
+    let url = Url::parse_with_params(&url, [("game_id", forum.to_string())])?;
+    let request: Request = http::Request::get(url.as_str()).body(Vec::new())?;
+    let response = client.execute(request).await?;
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()).into());
+    }
+
+    let body: Value = serde_json::from_slice(response.body())?;
+    debug!("HoYoLAB tools for forum {}: {}", forum, body);
+
+    match body["retcode"].as_i64() {
+        Some(0) | None => Ok(body),
+        Some(retcode) => Err(format!("Return code is {}", retcode).into()),
+    }
+}
+
+/// The first link to a check-in page under `path` with an act_id. The
+/// response's layout changes more often than the page links in it, so every
+/// string in it is a candidate.
+fn find(value: &Value, path: &str) -> Option<Discovered> {
+    match value {
+        Value::String(link) => {
+            let url = Url::parse(link).ok()?;
+
+            if !url.host_str()?.ends_with("hoyolab.com") || !url.path().contains(path) {
+                return None;
+            }
+
+            let act_id = url
+                .query_pairs()
+                .find(|(key, _)| key == "act_id")
+                .map(|(_, act_id)| act_id.into_owned())
+                .filter(|act_id| !act_id.is_empty())?;
+
+            Some(Discovered {
+                act_id,
+                url_page: link.clone(),
+            })
+        }
+        Value::Array(values) => values.iter().find_map(|value| find(value, path)),
+        Value::Object(values) => values.values().find_map(|value| find(value, path)),
+        _ => None,
+    }
+}
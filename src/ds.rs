@@ -0,0 +1,33 @@
+//! The "dynamic secret" the HoYoLAB web client signs game record and
+//! community requests with.
+
+use crate::clock;
+use http::{HeaderMap, HeaderValue};
+use md5::{Digest, Md5};
+
+const SALT: &str = "6s25p5ox5y14umn1p61aqyyvbvvl3lrt";
+
+/// Switches the request over to the web client's headers and signs it.
+/// `nonce` only has to vary between accounts.
+pub fn sign(headers: &mut HeaderMap, nonce: &str, lang: &str) {
+    headers.insert("x-rpc-app_version", HeaderValue::from_static("1.5.0"));
+    headers.insert("x-rpc-client_type", HeaderValue::from_static("5"));
+    if let Ok(lang) = HeaderValue::from_str(lang) {
+        headers.insert("x-rpc-language", lang);
+    }
+    headers.insert(
+        "DS",
+        HeaderValue::from_str(&dynamic_secret(nonce)).expect("Dynamic secret is ASCII"),
+    );
+}
+
+fn dynamic_secret(nonce: &str) -> String {
+    let time = clock::unix_now();
+    let random = &hex::encode(Md5::digest(format!("{}{}", time, nonce)))[..6];
+    let hash = hex::encode(Md5::digest(format!(
+        "salt={}&t={}&r={}",
+        SALT, time, random
+    )));
+
+    format!("{},{},{}", time, random, hash)
+}
@@ -0,0 +1,75 @@
+//! `--debug-dump`: every failed HoYoLAB exchange written to a file of its own,
+//! since an error like "expected value at line 1 column 1" says nothing about
+//! what the server actually sent.
+
+use crate::{
+    client::{Request, Response},
+    clock, redact_headers,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+use tracing::{debug, warn};
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+/// Tells apart the dumps of one second.
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts dumping failed exchanges into `dir`, creating it if needed.
+pub fn enable(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    DIR.set(dir.to_path_buf()).ok();
+    Ok(())
+}
+
+/// The request as it will be dumped, if dumping is enabled. Taken before
+/// sending, as the client consumes the request.
+pub fn request(request: &Request) -> Option<String> {
+    DIR.get()?;
+
+    Some(format!(
+        "{} {}\n{}\n\n{}",
+        request.method(),
+        request.uri(),
+        redact_headers(request.headers()).join("\n"),
+        String::from_utf8_lossy(request.body())
+    ))
+}
+
+/// Writes a dump of a failed exchange. `response` is `None` when none arrived.
+pub fn write(request: &str, response: Option<&Response>, error: &str) {
+    let Some(dir) = DIR.get() else {
+        return;
+    };
+
+    let response = match response {
+        Some(response) => format!(
+            "HTTP {}\n{}\n\n{}",
+            response.status(),
+            redact_headers(response.headers()).join("\n"),
+            String::from_utf8_lossy(response.body())
+        ),
+        None => "No response".to_string(),
+    };
+    let path = dir.join(format!(
+        "{}-{}.txt",
+        clock::unix_now(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    match fs::write(
+        &path,
+        format!(
+            "# {}\n\n## Request\n{}\n\n## Response\n{}\n",
+            error, request, response
+        ),
+    ) {
+        Ok(()) => debug!("Dumped the failed request to {}", path.display()),
+        Err(e) => warn!("Failed to write debug dump {}: {}", path.display(), e),
+    }
+}
@@ -0,0 +1,75 @@
+// Without the sentry feature the config is parsed but never used.
+#![cfg_attr(not(feature = "sentry"), allow(dead_code))]
+
+use crate::report::GameResult;
+#[cfg(feature = "sentry")]
+use crate::CheckinError;
+use serde::Deserialize;
+
+/// Reports panics and unexpected API errors to Sentry, for keeping an eye
+/// on many installations at once. Accounts are only identified by name.
+#[derive(Deserialize)]
+pub struct SentryConfig {
+    dsn: String,
+    /// e.g. `production`, to tell installations apart.
+    environment: Option<String>,
+}
+
+/// Keeps reporting on until dropped, then sends what is still queued.
+#[cfg(feature = "sentry")]
+pub type Guard = sentry::ClientInitGuard;
+
+#[cfg(not(feature = "sentry"))]
+pub enum Guard {}
+
+#[cfg(feature = "sentry")]
+pub fn init(config: &SentryConfig) -> Result<Guard, String> {
+    let dsn = config
+        .dsn
+        .parse()
+        .map_err(|e| format!("Invalid Sentry DSN: {}", e))?;
+
+    let mut options = sentry::ClientOptions::new();
+    options.dsn = Some(dsn);
+    options.release = sentry::release_name!();
+    options.environment = config.environment.clone().map(Into::into);
+
+    Ok(sentry::init(options))
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn init(_config: &SentryConfig) -> Result<Guard, String> {
+    Err("this binary was built without the sentry feature".to_string())
+}
+
+/// Reports a check-in that failed in a way the API should not have: an
+/// unknown retcode or response. Network failures, expired cookies and
+/// captchas are left to the logs, as there is nothing to fix in the code.
+#[cfg(feature = "sentry")]
+pub fn result(result: &GameResult) {
+    let Some(error) = result.error.as_ref().filter(|error| {
+        matches!(
+            error,
+            CheckinError::UnexpectedResponse(_)
+                | CheckinError::Api { .. }
+                | CheckinError::Unclaimed
+        )
+    }) else {
+        return;
+    };
+
+    sentry::with_scope(
+        |scope| {
+            scope.set_tag("game", &result.game);
+            scope.set_tag("account", &result.account);
+
+            if let Some(retcode) = result.retcode {
+                scope.set_tag("retcode", retcode);
+            }
+        },
+        || sentry::capture_message(&format!("{}: {}", result.game, error), sentry::Level::Error),
+    );
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn result(_result: &GameResult) {}
@@ -0,0 +1,68 @@
+//! The `--events` stream: one JSON object per line for each notable thing a
+//! run does, for piping into log processors such as Vector or jq.
+
+use crate::{
+    clock,
+    report::{GameResult, Status},
+};
+use serde_json::{json, Value};
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+use tracing::debug;
+
+static SINK: OnceLock<Mutex<Box<dyn Write + Send>>> = OnceLock::new();
+
+/// Starts writing events to `path`, appending to it, or to stdout for `-`.
+pub fn open(path: &Path) -> io::Result<()> {
+    let sink: Box<dyn Write + Send> = if path == Path::new("-") {
+        Box::new(io::stdout())
+    } else {
+        Box::new(OpenOptions::new().create(true).append(true).open(path)?)
+    };
+
+    SINK.set(Mutex::new(sink)).ok();
+    Ok(())
+}
+
+/// Writes an event with the given fields, if the stream is open.
+pub fn emit(event: &str, fields: Value) {
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+
+    let mut line = json!({
+        "time": clock::rfc3339(clock::unix_now()),
+        "event": event,
+    });
+
+    if let (Some(line), Value::Object(fields)) = (line.as_object_mut(), fields) {
+        line.extend(fields);
+    }
+
+    let mut sink = sink.lock().expect("Event stream lock poisoned");
+
+    if let Err(e) = writeln!(sink, "{}", line).and_then(|_| sink.flush()) {
+        debug!("Failed to write event: {}", e);
+    }
+}
+
+/// Emits the outcome of a check-in, named after how it ended.
+pub fn result(result: &GameResult) {
+    let event = match result.status {
+        Status::Claimed => "sign_succeeded",
+        Status::AlreadyClaimed => "already_signed",
+        Status::WouldClaim => "would_sign",
+        Status::CaptchaRequired => "captcha_required",
+        Status::CookieExpired => "cookie_expired",
+        Status::Failed => "sign_failed",
+    };
+
+    emit(
+        event,
+        serde_json::to_value(result).expect("Failed to serialize result"),
+    );
+}
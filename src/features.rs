@@ -0,0 +1,74 @@
+/// Compile-time features, so users of prebuilt binaries can tell why a
+/// config option has no effect.
+const FEATURES: &[(&str, bool, &str)] = &[
+    (
+        "reqwest-blocking",
+        cfg!(feature = "reqwest-blocking"),
+        "Blocking HTTP client used by the command-line binary",
+    ),
+    (
+        "reqwest-async",
+        cfg!(feature = "reqwest-async"),
+        "Async HTTP client for Tokio runtimes",
+    ),
+    (
+        "fetch",
+        cfg!(feature = "fetch"),
+        "HTTP client for wasm hosts' fetch()",
+    ),
+    (
+        "server",
+        cfg!(feature = "server"),
+        "`server` config: /metrics and the cookie refresh page in daemon mode",
+    ),
+    (
+        "otlp",
+        cfg!(feature = "otlp"),
+        "`otlp` config: OpenTelemetry trace export",
+    ),
+    (
+        "sentry",
+        cfg!(feature = "sentry"),
+        "`sentry` config: reporting panics and unexpected API errors",
+    ),
+    (
+        "lambda",
+        cfg!(feature = "lambda"),
+        "--lambda: running as an AWS Lambda custom runtime",
+    ),
+    (
+        "impersonate",
+        cfg!(feature = "impersonate"),
+        "`impersonate` config: Chrome's TLS and HTTP/2 fingerprint",
+    ),
+    (
+        "browser-cookies",
+        cfg!(feature = "browser-cookies"),
+        "import-cookies: reading cookies from Chrome and Firefox profiles",
+    ),
+];
+
+pub fn report() -> String {
+    let width = FEATURES
+        .iter()
+        .map(|(name, ..)| name.len())
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec![format!(
+        "{} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )];
+
+    lines.extend(FEATURES.iter().map(|(name, enabled, description)| {
+        format!(
+            "{}{:width$}  {}",
+            if *enabled { '+' } else { '-' },
+            name,
+            description,
+            width = width
+        )
+    }));
+
+    lines.join("\n")
+}
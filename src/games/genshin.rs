@@ -0,0 +1,127 @@
+use super::Game;
+use crate::notes::{self, Meter};
+use crate::{GeetestRisk, SignData};
+use serde::Deserialize;
+use serde_json::Value;
+
+pub struct Genshin;
+
+impl Game for Genshin {
+    fn id(&self) -> &'static str {
+        "genshin"
+    }
+
+    fn name(&self) -> &'static str {
+        "Genshin Impact"
+    }
+
+    fn game_biz(&self) -> &'static str {
+        "hk4e_global"
+    }
+
+    fn hoyolab_id(&self) -> u32 {
+        2
+    }
+
+    fn act_id(&self) -> &'static str {
+        "e202102251931481"
+    }
+
+    fn url_get_status(&self) -> &'static str {
+        "https://sg-hk4e-api.hoyolab.com/event/sol/info"
+    }
+
+    fn url_sign(&self) -> &'static str {
+        "https://sg-hk4e-api.hoyolab.com/event/sol/sign"
+    }
+
+    fn url_home(&self) -> &'static str {
+        "https://sg-hk4e-api.hoyolab.com/event/sol/home"
+    }
+
+    fn url_resign_info(&self) -> Option<&'static str> {
+        Some("https://sg-hk4e-api.hoyolab.com/event/sol/resign_info")
+    }
+
+    fn url_resign(&self) -> Option<&'static str> {
+        Some("https://sg-hk4e-api.hoyolab.com/event/sol/resign")
+    }
+
+    fn url_redeem(&self) -> Option<&'static str> {
+        Some("https://sg-hk4e-api.hoyoverse.com/common/apicdkey/api/webExchangeCdkey")
+    }
+
+    fn url_page(&self) -> &'static str {
+        "https://act.hoyolab.com/ys/event/signin-sea-v3/index.html?act_id=e202102251931481"
+    }
+
+    /// Genshin predates luna and reports the challenge inline.
+    fn sign_risk(&self, data: SignData) -> Option<GeetestRisk> {
+        Some(data.risk)
+    }
+
+    fn url_notes(&self) -> Option<&'static str> {
+        Some("https://bbs-api-os.hoyolab.com/game_record/genshin/api/dailyNote")
+    }
+
+    fn notes(&self, data: Value) -> Result<Vec<Meter>, serde_json::Error> {
+        let note: DailyNote = serde_json::from_value(data)?;
+        let mut meters = vec![
+            Meter {
+                name: "Original Resin",
+                current: note.current_resin,
+                max: note.max_resin,
+                full_in: note.resin_recovery_time,
+            },
+            Meter {
+                name: "Realm Currency",
+                current: note.current_home_coin,
+                max: note.max_home_coin,
+                full_in: note.home_coin_recovery_time,
+            },
+        ];
+
+        if !note.expeditions.is_empty() {
+            meters.push(Meter {
+                name: "Expeditions",
+                current: note
+                    .expeditions
+                    .iter()
+                    .filter(|expedition| expedition.status == "Finished")
+                    .count() as u32,
+                max: note.expeditions.len() as u32,
+                full_in: note
+                    .expeditions
+                    .iter()
+                    .map(|expedition| expedition.remained_time)
+                    .max()
+                    .unwrap_or(0),
+            });
+        }
+
+        Ok(meters)
+    }
+}
+
+#[derive(Deserialize)]
+struct DailyNote {
+    current_resin: u32,
+    max_resin: u32,
+    #[serde(deserialize_with = "notes::seconds")]
+    resin_recovery_time: u64,
+    #[serde(default)]
+    current_home_coin: u32,
+    #[serde(default)]
+    max_home_coin: u32,
+    #[serde(default, deserialize_with = "notes::seconds")]
+    home_coin_recovery_time: u64,
+    #[serde(default)]
+    expeditions: Vec<Expedition>,
+}
+
+#[derive(Deserialize)]
+struct Expedition {
+    status: String,
+    #[serde(deserialize_with = "notes::seconds")]
+    remained_time: u64,
+}
@@ -0,0 +1,83 @@
+//! The games HoYoLAB runs daily check-ins for. Each title lives in its own
+//! module and only overrides what differs from the luna check-in API most of
+//! them share.
+
+mod genshin;
+mod overrides;
+mod star_rail;
+mod zenless;
+
+pub use genshin::Genshin;
+pub use overrides::{configure, GameOverrides};
+
+use crate::{notes::Meter, GeetestRisk, SignData};
+use http::HeaderMap;
+use serde_json::Value;
+
+pub trait Game {
+    /// Stable identifier, accepted by `--game` alongside the display name.
+    fn id(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    /// HoYoverse's identifier for the global release, used by the account APIs.
+    fn game_biz(&self) -> &'static str;
+    /// HoYoLAB's number for the game, in its forum and game record APIs.
+    fn hoyolab_id(&self) -> u32;
+    fn act_id(&self) -> &'static str;
+    fn url_get_status(&self) -> &'static str;
+    fn url_sign(&self) -> &'static str;
+    /// The month's reward calendar.
+    fn url_home(&self) -> &'static str;
+
+    /// The web check-in page, where a captcha can be solved by checking in by hand.
+    fn url_page(&self) -> &'static str;
+
+    /// Event bonus endpoint of the luna API. `GET` reports whether a bonus
+    /// phase is running, `POST` claims it.
+    fn url_extra_award(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Make-up check-in endpoints, which spend HoYoLAB coins to recover
+    /// missed days. `url_resign_info` reports the limits and price.
+    fn url_resign_info(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn url_resign(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Gift code redemption endpoint.
+    fn url_redeem(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Real-Time Notes endpoint of the game record API.
+    fn url_notes(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The meters worth watching in a Real-Time Notes response.
+    fn notes(&self, _data: Value) -> Result<Vec<Meter>, serde_json::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Adds the headers this game needs on top of the common ones.
+    fn headers(&self, _headers: &mut HeaderMap) {}
+
+    /// The Geetest challenge of a sign response. The luna games nest it.
+    fn sign_risk(&self, data: SignData) -> Option<GeetestRisk> {
+        data.gt_result
+    }
+}
+
+/// Every supported game, in the order they are checked in.
+pub const GAMES: &[&dyn Game] = &[&Genshin, &star_rail::StarRail, &zenless::ZenlessZoneZero];
+
+/// Looks up a game by id or display name, ignoring case.
+pub fn find(name: &str) -> Option<&'static dyn Game> {
+    GAMES
+        .iter()
+        .copied()
+        .find(|game| game.id().eq_ignore_ascii_case(name) || game.name().eq_ignore_ascii_case(name))
+}
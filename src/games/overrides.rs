@@ -0,0 +1,142 @@
+use super::Game;
+use crate::{discovery::Discovered, notes::Meter, GeetestRisk, SignData};
+use http::HeaderMap;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Replacements for a built-in game's `act_id` and endpoints, set under the
+/// game's id in the config's `games`. HoYoverse rotates act_ids now and then,
+/// and a config tweak is quicker than waiting for a release.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct GameOverrides {
+    act_id: Option<String>,
+    url_get_status: Option<String>,
+    url_sign: Option<String>,
+    url_home: Option<String>,
+    url_page: Option<String>,
+    url_extra_award: Option<String>,
+    url_resign_info: Option<String>,
+    url_resign: Option<String>,
+    url_redeem: Option<String>,
+    url_notes: Option<String>,
+}
+
+impl GameOverrides {
+    /// Takes a discovered act_id and check-in page, unless they are set
+    /// explicitly.
+    pub fn discovered(&mut self, discovered: Discovered) {
+        self.act_id.get_or_insert(discovered.act_id);
+        self.url_page.get_or_insert(discovered.url_page);
+    }
+}
+
+/// A built-in game with some of its act_id and endpoints replaced.
+struct Overridden {
+    game: &'static dyn Game,
+    act_id: Option<&'static str>,
+    url_get_status: Option<&'static str>,
+    url_sign: Option<&'static str>,
+    url_home: Option<&'static str>,
+    url_page: Option<&'static str>,
+    url_extra_award: Option<&'static str>,
+    url_resign_info: Option<&'static str>,
+    url_resign: Option<&'static str>,
+    url_redeem: Option<&'static str>,
+    url_notes: Option<&'static str>,
+}
+
+/// `game` with `overrides` applied. The overrides live as long as the
+/// process, like the built-in games, so this is only meant to be done once
+/// at startup.
+pub fn configure(game: &'static dyn Game, overrides: Option<&GameOverrides>) -> &'static dyn Game {
+    let Some(overrides) = overrides else {
+        return game;
+    };
+    let leak = |value: &Option<String>| value.clone().map(|value| &*value.leak());
+
+    Box::leak(Box::new(Overridden {
+        game,
+        act_id: leak(&overrides.act_id),
+        url_get_status: leak(&overrides.url_get_status),
+        url_sign: leak(&overrides.url_sign),
+        url_home: leak(&overrides.url_home),
+        url_page: leak(&overrides.url_page),
+        url_extra_award: leak(&overrides.url_extra_award),
+        url_resign_info: leak(&overrides.url_resign_info),
+        url_resign: leak(&overrides.url_resign),
+        url_redeem: leak(&overrides.url_redeem),
+        url_notes: leak(&overrides.url_notes),
+    }))
+}
+
+impl Game for Overridden {
+    fn id(&self) -> &'static str {
+        self.game.id()
+    }
+
+    fn name(&self) -> &'static str {
+        self.game.name()
+    }
+
+    fn game_biz(&self) -> &'static str {
+        self.game.game_biz()
+    }
+
+    fn hoyolab_id(&self) -> u32 {
+        self.game.hoyolab_id()
+    }
+
+    fn act_id(&self) -> &'static str {
+        self.act_id.unwrap_or_else(|| self.game.act_id())
+    }
+
+    fn url_get_status(&self) -> &'static str {
+        self.url_get_status
+            .unwrap_or_else(|| self.game.url_get_status())
+    }
+
+    fn url_sign(&self) -> &'static str {
+        self.url_sign.unwrap_or_else(|| self.game.url_sign())
+    }
+
+    fn url_home(&self) -> &'static str {
+        self.url_home.unwrap_or_else(|| self.game.url_home())
+    }
+
+    fn url_page(&self) -> &'static str {
+        self.url_page.unwrap_or_else(|| self.game.url_page())
+    }
+
+    fn url_extra_award(&self) -> Option<&'static str> {
+        self.url_extra_award.or_else(|| self.game.url_extra_award())
+    }
+
+    fn url_resign_info(&self) -> Option<&'static str> {
+        self.url_resign_info.or_else(|| self.game.url_resign_info())
+    }
+
+    fn url_resign(&self) -> Option<&'static str> {
+        self.url_resign.or_else(|| self.game.url_resign())
+    }
+
+    fn url_redeem(&self) -> Option<&'static str> {
+        self.url_redeem.or_else(|| self.game.url_redeem())
+    }
+
+    fn url_notes(&self) -> Option<&'static str> {
+        self.url_notes.or_else(|| self.game.url_notes())
+    }
+
+    fn notes(&self, data: Value) -> Result<Vec<Meter>, serde_json::Error> {
+        self.game.notes(data)
+    }
+
+    fn headers(&self, headers: &mut HeaderMap) {
+        self.game.headers(headers)
+    }
+
+    fn sign_risk(&self, data: SignData) -> Option<GeetestRisk> {
+        self.game.sign_risk(data)
+    }
+}
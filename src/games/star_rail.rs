@@ -0,0 +1,109 @@
+use super::Game;
+use crate::notes::Meter;
+use serde::Deserialize;
+use serde_json::Value;
+
+pub struct StarRail;
+
+impl Game for StarRail {
+    fn id(&self) -> &'static str {
+        "hsr"
+    }
+
+    fn name(&self) -> &'static str {
+        "Honkai Star Rail"
+    }
+
+    fn game_biz(&self) -> &'static str {
+        "hkrpg_global"
+    }
+
+    fn hoyolab_id(&self) -> u32 {
+        6
+    }
+
+    fn act_id(&self) -> &'static str {
+        "e202303301540311"
+    }
+
+    fn url_get_status(&self) -> &'static str {
+        "https://sg-public-api.hoyolab.com/event/luna/os/info"
+    }
+
+    fn url_sign(&self) -> &'static str {
+        "https://sg-public-api.hoyolab.com/event/luna/os/sign"
+    }
+
+    fn url_home(&self) -> &'static str {
+        "https://sg-public-api.hoyolab.com/event/luna/os/home"
+    }
+
+    fn url_resign_info(&self) -> Option<&'static str> {
+        Some("https://sg-public-api.hoyolab.com/event/luna/os/resign_info")
+    }
+
+    fn url_resign(&self) -> Option<&'static str> {
+        Some("https://sg-public-api.hoyolab.com/event/luna/os/resign")
+    }
+
+    fn url_redeem(&self) -> Option<&'static str> {
+        Some("https://sg-hkrpg-api.hoyoverse.com/common/apicdkey/api/webExchangeCdkey")
+    }
+
+    fn url_page(&self) -> &'static str {
+        "https://act.hoyolab.com/bbs/event/signin/hkrpg/index.html?act_id=e202303301540311"
+    }
+
+    fn url_extra_award(&self) -> Option<&'static str> {
+        Some("https://sg-public-api.hoyolab.com/event/luna/os/extra_award")
+    }
+
+    fn url_notes(&self) -> Option<&'static str> {
+        Some("https://bbs-api-os.hoyolab.com/game_record/hkrpg/api/note")
+    }
+
+    fn notes(&self, data: Value) -> Result<Vec<Meter>, serde_json::Error> {
+        let note: Note = serde_json::from_value(data)?;
+        let mut meters = vec![Meter {
+            name: "Trailblaze Power",
+            current: note.current_stamina,
+            max: note.max_stamina,
+            full_in: note.stamina_recover_time,
+        }];
+
+        if !note.expeditions.is_empty() {
+            meters.push(Meter {
+                name: "Assignments",
+                current: note
+                    .expeditions
+                    .iter()
+                    .filter(|expedition| expedition.status == "Finished")
+                    .count() as u32,
+                max: note.expeditions.len() as u32,
+                full_in: note
+                    .expeditions
+                    .iter()
+                    .map(|expedition| expedition.remaining_time)
+                    .max()
+                    .unwrap_or(0),
+            });
+        }
+
+        Ok(meters)
+    }
+}
+
+#[derive(Deserialize)]
+struct Note {
+    current_stamina: u32,
+    max_stamina: u32,
+    stamina_recover_time: u64,
+    #[serde(default)]
+    expeditions: Vec<Expedition>,
+}
+
+#[derive(Deserialize)]
+struct Expedition {
+    status: String,
+    remaining_time: u64,
+}
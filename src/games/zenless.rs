@@ -0,0 +1,98 @@
+use super::Game;
+use crate::notes::Meter;
+use http::{HeaderMap, HeaderValue};
+use serde::Deserialize;
+use serde_json::Value;
+
+pub struct ZenlessZoneZero;
+
+impl Game for ZenlessZoneZero {
+    fn id(&self) -> &'static str {
+        "zzz"
+    }
+
+    fn name(&self) -> &'static str {
+        "Zenless Zone Zero"
+    }
+
+    fn game_biz(&self) -> &'static str {
+        "nap_global"
+    }
+
+    fn hoyolab_id(&self) -> u32 {
+        8
+    }
+
+    fn act_id(&self) -> &'static str {
+        "e202406031448091"
+    }
+
+    fn url_get_status(&self) -> &'static str {
+        "https://sg-public-api.hoyolab.com/event/luna/zzz/os/info"
+    }
+
+    fn url_sign(&self) -> &'static str {
+        "https://sg-public-api.hoyolab.com/event/luna/zzz/os/sign"
+    }
+
+    fn url_home(&self) -> &'static str {
+        "https://sg-public-api.hoyolab.com/event/luna/zzz/os/home"
+    }
+
+    fn url_resign_info(&self) -> Option<&'static str> {
+        Some("https://sg-public-api.hoyolab.com/event/luna/zzz/os/resign_info")
+    }
+
+    fn url_resign(&self) -> Option<&'static str> {
+        Some("https://sg-public-api.hoyolab.com/event/luna/zzz/os/resign")
+    }
+
+    fn url_redeem(&self) -> Option<&'static str> {
+        Some("https://public-operation-nap.hoyoverse.com/common/apicdkey/api/webExchangeCdkey")
+    }
+
+    fn url_page(&self) -> &'static str {
+        "https://act.hoyolab.com/bbs/event/signin/zzz/e202406031448091.html?act_id=e202406031448091"
+    }
+
+    fn url_extra_award(&self) -> Option<&'static str> {
+        Some("https://sg-public-api.hoyolab.com/event/luna/zzz/os/extra_award")
+    }
+
+    fn headers(&self, headers: &mut HeaderMap) {
+        headers.insert("x-rpc-signgame", HeaderValue::from_static("zzz"));
+    }
+
+    fn url_notes(&self) -> Option<&'static str> {
+        Some("https://sg-public-api.hoyolab.com/event/game_record_zzz/api/zzz/note")
+    }
+
+    fn notes(&self, data: Value) -> Result<Vec<Meter>, serde_json::Error> {
+        let note: Note = serde_json::from_value(data)?;
+
+        Ok(vec![Meter {
+            name: "Battery Charge",
+            current: note.energy.progress.current,
+            max: note.energy.progress.max,
+            full_in: note.energy.restore,
+        }])
+    }
+}
+
+#[derive(Deserialize)]
+struct Note {
+    energy: Energy,
+}
+
+#[derive(Deserialize)]
+struct Energy {
+    progress: Progress,
+    /// Seconds until full.
+    restore: u64,
+}
+
+#[derive(Deserialize)]
+struct Progress {
+    current: u32,
+    max: u32,
+}
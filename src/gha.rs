@@ -0,0 +1,73 @@
+//! `--gha`: workflow annotations and a job summary, for runs on a scheduled
+//! GitHub Actions workflow.
+
+use crate::report::{self, GameResult, RewardKind, Status};
+use std::{env, fs::OpenOptions, io::Write};
+use tracing::warn;
+
+/// Prints a `::notice` for each claimed reward and an `::error` for each
+/// failed check-in, so they show up on the workflow run's page.
+pub fn annotate(report: &[GameResult]) {
+    for result in report {
+        let kind = match result.kind {
+            RewardKind::Daily => "",
+            RewardKind::Extra => " (bonus)",
+        };
+        let title = format!("{}: {}{}", result.account, result.game, kind);
+
+        if result.status == Status::Claimed {
+            println!(
+                "::notice title={}::{}",
+                escape_property(&title),
+                escape(&format!(
+                    "Claimed {}",
+                    result.reward.as_deref().unwrap_or("the reward")
+                ))
+            );
+        } else if !result.is_success() {
+            println!(
+                "::error title={}::{}",
+                escape_property(&title),
+                escape(result.message.as_deref().unwrap_or(result.status.label()))
+            );
+        }
+    }
+}
+
+/// Appends the run's account × game table to the job summary. Does nothing
+/// outside of GitHub Actions, where `GITHUB_STEP_SUMMARY` is unset.
+pub fn write_summary(report: &[GameResult]) {
+    let Some(path) = env::var_os("GITHUB_STEP_SUMMARY") else {
+        return;
+    };
+
+    let written = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&path)
+        .and_then(|mut file| {
+            writeln!(
+                file,
+                "## HoYoLAB check-in\n\n{}\n",
+                report::markdown_table(report)
+            )
+        });
+
+    if let Err(e) = written {
+        warn!("Failed to write the job summary: {}", e);
+    }
+}
+
+/// Escapes an annotation's message, which ends at the first newline.
+fn escape(message: &str) -> String {
+    message
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes an annotation property such as the title, which also ends at `:`
+/// or `,`.
+fn escape_property(value: &str) -> String {
+    escape(value).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,239 @@
+use crate::{
+    report::{self, GameResult},
+    CheckinError,
+};
+use http::{header::CONTENT_TYPE, HeaderMap, Method};
+use reqwest::blocking::Client;
+use serde::{Deserialize, Deserializer};
+use tracing::{debug, warn};
+use url::form_urlencoded;
+
+/// Either a single healthcheck URL, one per outcome class so monitoring
+/// can distinguish "refresh your cookies" from "HoYoLAB is down", or a
+/// request of its own. Only the latter two can tolerate some failures.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Healthcheck {
+    Url(String),
+    Request(PingRequest),
+    Outcomes(OutcomeUrls),
+}
+
+/// A ping shaped for services that take neither the `/fail` convention nor
+/// a plain text body, e.g. an Uptime Kuma push monitor. The URL and body
+/// are templates: `{status}` becomes `up` or `down`, `{success}` `true` or
+/// `false`, `{failed}` the number of failed check-ins and `{summary}` the
+/// results table. Values are URL-encoded in the URL, and escaped as JSON
+/// string contents when the `Content-Type` is JSON.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PingRequest {
+    url: String,
+    #[serde(deserialize_with = "method")]
+    method: Method,
+    #[serde(default, deserialize_with = "crate::header_map")]
+    headers: HeaderMap,
+    #[serde(default)]
+    body: String,
+    /// How many account/game check-ins may fail with the run still up.
+    #[serde(default)]
+    allowed_failures: usize,
+}
+
+fn method<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Method, D::Error> {
+    let method = String::deserialize(deserializer)?;
+
+    Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+        .map_err(|_| serde::de::Error::custom(format!("invalid method {:?}", method)))
+}
+
+#[derive(Deserialize)]
+pub struct OutcomeUrls {
+    /// Pinged on success, and with `/fail` for failures not covered below.
+    url: String,
+    cookie_expired: Option<String>,
+    captcha: Option<String>,
+    network: Option<String>,
+    /// How many account/game check-ins may fail before anything is pinged
+    /// with `/fail`, so one flaky account does not page anyone.
+    #[serde(default)]
+    allowed_failures: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    CookieExpired,
+    Captcha,
+    Network,
+    Other,
+}
+
+impl Outcome {
+    fn of(error: &CheckinError) -> Self {
+        match error {
+            CheckinError::Network(_) => Self::Network,
+            CheckinError::CookieExpired { .. } => Self::CookieExpired,
+            CheckinError::CaptchaRequired { .. } => Self::Captcha,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl Healthcheck {
+    fn urls(&self) -> Vec<&str> {
+        match self {
+            Self::Url(url) => vec![url],
+            Self::Request(_) => Vec::new(),
+            Self::Outcomes(urls) => [
+                Some(&urls.url),
+                urls.cookie_expired.as_ref(),
+                urls.captcha.as_ref(),
+                urls.network.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect(),
+        }
+    }
+
+    /// Signals that a run began, so run times show up and a run that hangs
+    /// is flagged. Not done for a `PingRequest`, which has no such signal.
+    pub fn start(&self, client: &Client) {
+        for url in self.urls() {
+            post(client, &format!("{}/start", url), String::new());
+        }
+    }
+
+    /// Reports the run. Every outcome-specific URL is pinged on each run, with
+    /// `/fail` only if that class of failure occurred, so each check recovers
+    /// on its own once the problem is fixed.
+    pub fn ping(&self, client: &Client, results: &[GameResult]) {
+        let mut outcomes: Vec<Outcome> = results
+            .iter()
+            .filter_map(|result| result.error.as_ref().map(Outcome::of))
+            .collect();
+        let body = body(results);
+
+        let allowed_failures = match self {
+            Self::Url(_) => 0,
+            Self::Request(request) => request.allowed_failures,
+            Self::Outcomes(urls) => urls.allowed_failures,
+        };
+
+        if !outcomes.is_empty() && outcomes.len() <= allowed_failures {
+            debug!(
+                "{} failed check-ins are within allowed_failures, reporting success",
+                outcomes.len()
+            );
+            outcomes.clear();
+        }
+
+        match self {
+            Self::Url(url) => send(client, url, !outcomes.is_empty(), &body),
+            Self::Request(request) => request.send(client, outcomes.len(), &body),
+            Self::Outcomes(urls) => {
+                let mut unhandled = false;
+
+                for (outcome, url) in [
+                    (Outcome::CookieExpired, &urls.cookie_expired),
+                    (Outcome::Captcha, &urls.captcha),
+                    (Outcome::Network, &urls.network),
+                    (Outcome::Other, &None),
+                ] {
+                    let failed = outcomes.contains(&outcome);
+
+                    match url {
+                        Some(url) => send(client, url, failed, &body),
+                        None => unhandled |= failed,
+                    }
+                }
+
+                send(client, &urls.url, unhandled, &body);
+            }
+        }
+    }
+}
+
+impl PingRequest {
+    fn send(&self, client: &Client, failed: usize, summary: &str) {
+        let values = [
+            ("status", if failed == 0 { "up" } else { "down" }),
+            ("success", if failed == 0 { "true" } else { "false" }),
+            ("failed", &failed.to_string()),
+            ("summary", summary),
+        ];
+        let json = self
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("json"));
+
+        let url = render(&self.url, &values, |value| {
+            form_urlencoded::byte_serialize(value.as_bytes()).collect()
+        });
+        let body = render(&self.body, &values, |value| {
+            if json {
+                let quoted = serde_json::to_string(value).expect("Strings serialize");
+                quoted[1..quoted.len() - 1].to_string()
+            } else {
+                value.to_string()
+            }
+        });
+
+        debug!("Pinging healthcheck {} {}", self.method, url);
+
+        if let Err(e) = client
+            .request(self.method.clone(), &url)
+            .headers(self.headers.clone())
+            .body(body)
+            .send()
+            .and_then(|response| response.error_for_status())
+        {
+            warn!("Failed to ping healthcheck: {}", e);
+        }
+    }
+}
+
+/// Fills in a template's `{name}` placeholders with `escape`d values.
+pub fn render(template: &str, values: &[(&str, &str)], escape: impl Fn(&str) -> String) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), &escape(value))
+        })
+}
+
+/// The results, shown with the ping on the healthcheck's page.
+fn body(results: &[GameResult]) -> String {
+    let mut body = report::summary_table(results, false);
+
+    for result in results.iter().filter(|result| !result.is_success()) {
+        body.push_str(&format!(
+            "\n{} / {}: {}",
+            result.account,
+            result.game,
+            result.message.as_deref().unwrap_or("failed")
+        ));
+    }
+
+    body
+}
+
+fn send(client: &Client, url: &str, failed: bool, body: &str) {
+    let url = if failed {
+        format!("{}/fail", url)
+    } else {
+        url.to_string()
+    };
+
+    post(client, &url, body.to_string());
+}
+
+fn post(client: &Client, url: &str, body: String) {
+    debug!("Pinging healthcheck {}", url);
+
+    if let Err(e) = client.post(url).body(body).send() {
+        warn!("Failed to ping healthcheck: {}", e);
+    }
+}
@@ -0,0 +1,92 @@
+//! `--lambda`: serves AWS Lambda's runtime API as a custom runtime, with the
+//! binary deployed as `bootstrap`. Every invocation, e.g. from an
+//! EventBridge schedule, runs one check-in pass and answers with its results.
+//! Cloudflare Workers run the library's `fetch` backend instead, see
+//! `examples/cloudflare_workers`.
+
+use crate::report::GameResult;
+#[cfg(feature = "lambda")]
+use reqwest::blocking::Client;
+#[cfg(feature = "lambda")]
+use serde_json::json;
+#[cfg(feature = "lambda")]
+use std::env;
+#[cfg(feature = "lambda")]
+use tracing::{info, warn};
+
+/// Handles invocations with `pass` until the runtime API fails, then
+/// returns why.
+#[cfg(feature = "lambda")]
+pub fn serve(mut pass: impl FnMut() -> Vec<GameResult>) -> String {
+    let Ok(api) = env::var("AWS_LAMBDA_RUNTIME_API") else {
+        return "AWS_LAMBDA_RUNTIME_API is not set, not running on Lambda".to_string();
+    };
+    let base = format!("http://{}/2018-06-01/runtime", api);
+
+    // Asking for the next invocation blocks until there is one.
+    let client = match Client::builder().timeout(None).build() {
+        Ok(client) => client,
+        Err(e) => return e.to_string(),
+    };
+
+    loop {
+        let invocation = match client
+            .get(format!("{}/invocation/next", base))
+            .send()
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(invocation) => invocation,
+            Err(e) => return format!("Failed to get the next invocation: {}", e),
+        };
+        let Some(id) = invocation
+            .headers()
+            .get("Lambda-Runtime-Aws-Request-Id")
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_string)
+        else {
+            return "Invocation without a request id".to_string();
+        };
+
+        info!("Handling invocation {}", id);
+        let report = pass();
+
+        if let Err(e) = client
+            .post(format!("{}/invocation/{}/response", base, id))
+            .json(&json!({
+                "success": report.iter().all(GameResult::is_success),
+                "results": report,
+            }))
+            .send()
+            .and_then(|response| response.error_for_status())
+        {
+            warn!("Failed to answer invocation {}: {}", id, e);
+        }
+    }
+}
+
+/// Reports a failure to start to the runtime API, as the reason the function
+/// could not be initialized.
+#[cfg(feature = "lambda")]
+pub fn init_error(message: &str) {
+    let Ok(api) = env::var("AWS_LAMBDA_RUNTIME_API") else {
+        return;
+    };
+
+    if let Err(e) = Client::new()
+        .post(format!("http://{}/2018-06-01/runtime/init/error", api))
+        .header("Lambda-Runtime-Function-Error-Type", "Runtime.ConfigError")
+        .json(&json!({ "errorMessage": message, "errorType": "Runtime.ConfigError" }))
+        .send()
+        .and_then(|response| response.error_for_status())
+    {
+        warn!("Failed to report the init error: {}", e);
+    }
+}
+
+#[cfg(not(feature = "lambda"))]
+pub fn serve(_pass: impl FnMut() -> Vec<GameResult>) -> String {
+    "this binary was built without the lambda feature".to_string()
+}
+
+#[cfg(not(feature = "lambda"))]
+pub fn init_error(_message: &str) {}
@@ -0,0 +1,5 @@
+//! The HTTP layer of the check-in, for hosts other than the command-line
+//! binary: async runtimes with `reqwest-async`, and wasm runtimes such as
+//! Cloudflare Workers with `fetch` (see `examples/cloudflare_workers`).
+
+pub mod client;
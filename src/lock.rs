@@ -0,0 +1,77 @@
+//! Keeps two instances from checking in the same accounts at once, e.g. when
+//! cron starts the next run while a slow one is still going.
+
+use std::{
+    fmt,
+    fs::{File, OpenOptions, TryLockError},
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Held for as long as the instance runs. The OS releases it when the
+/// process exits, however it exits, so a crash never leaves it stale.
+pub struct RunLock {
+    _file: File,
+}
+
+pub enum LockError {
+    Held { path: PathBuf, pid: Option<u32> },
+    Io { path: PathBuf, error: io::Error },
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Held { path, pid } => {
+                write!(f, "Another instance is already running")?;
+
+                if let Some(pid) = pid {
+                    write!(f, " (pid {})", pid)?;
+                }
+
+                write!(f, ", it holds {}", path.display())
+            }
+            Self::Io { path, error } => {
+                write!(f, "Failed to lock {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+/// Locks `path`, creating it if needed, and writes our pid into it for
+/// whoever finds it locked.
+pub fn acquire(path: &Path) -> Result<RunLock, LockError> {
+    let io_error = |error| LockError::Io {
+        path: path.to_path_buf(),
+        error,
+    };
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(io_error)?;
+
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let mut pid = String::new();
+            file.read_to_string(&mut pid).ok();
+
+            return Err(LockError::Held {
+                path: path.to_path_buf(),
+                pid: pid.trim().parse().ok(),
+            });
+        }
+        Err(TryLockError::Error(error)) => return Err(io_error(error)),
+    }
+
+    file.set_len(0)
+        .and_then(|()| file.rewind())
+        .and_then(|()| write!(file, "{}", process::id()))
+        .map_err(io_error)?;
+
+    Ok(RunLock { _file: file })
+}
@@ -0,0 +1,213 @@
+use crate::{
+    clock, progress,
+    telemetry::{self, OtlpConfig},
+};
+use serde::Deserialize;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+use tracing::{warn, Level, Metadata};
+use tracing_subscriber::{
+    field::RecordFields,
+    filter::filter_fn,
+    fmt::{
+        self,
+        format::{DefaultFields, Writer},
+        FormatFields,
+    },
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+/// Optional log file, for hosts without journald or a container runtime
+/// collecting stdout.
+#[derive(Deserialize, Clone)]
+pub struct LogFile {
+    path: PathBuf,
+    /// Rotate once the file grows beyond this many bytes.
+    max_bytes: Option<u64>,
+    /// Rotate when the (UTC) day changes.
+    #[serde(default)]
+    daily: bool,
+    /// Number of rotated files (`path.1`, `path.2`, ...) to keep.
+    #[serde(default = "default_keep")]
+    keep: usize,
+}
+
+fn default_keep() -> usize {
+    5
+}
+
+/// Sets up the global subscriber. Logs always go to the console (stderr when
+/// stdout is reserved for machine-readable output), colored with `color`, and
+/// optionally to a file, spans optionally to an OTLP collector. `RUST_LOG`
+/// takes precedence over `verbosity`.
+pub fn init(
+    to_stderr: bool,
+    color: bool,
+    verbosity: i8,
+    file: Option<&LogFile>,
+    otlp: Option<&OtlpConfig>,
+) {
+    // Quiet mode still enables our info-level spans so errors keep their
+    // account and game context; the events themselves are filtered below.
+    let directives = match verbosity {
+        ..=-1 => "error,hoyo_checkin_rs=info",
+        0 => "info",
+        1 => "info,hoyo_checkin_rs=debug",
+        2.. => "debug,hoyo_checkin_rs=trace",
+    };
+
+    let console = fmt::layer()
+        .with_target(false)
+        .with_ansi(color)
+        .with_writer(move || -> Box<dyn Write> {
+            if to_stderr {
+                Box::new(progress::Writer(io::stderr()))
+            } else {
+                Box::new(progress::Writer(io::stdout()))
+            }
+        })
+        .with_filter(filter_fn(not_trace_only));
+
+    let (file, file_error) = match file.map(RotatingFile::open).transpose() {
+        Ok(file) => (file, None),
+        Err(e) => (None, Some(e)),
+    };
+
+    let (otlp, otlp_error) = match otlp.map(telemetry::layer).transpose() {
+        Ok(otlp) => (otlp, None),
+        Err(e) => (None, Some(e)),
+    };
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(directives)))
+        .with(filter_fn(move |metadata| {
+            verbosity >= 0 || metadata.is_span() || *metadata.level() == Level::ERROR
+        }))
+        .with(console)
+        .with(file.map(|file| {
+            fmt::layer()
+                .with_target(false)
+                .with_ansi(false)
+                .fmt_fields(PlainFields::default())
+                .with_writer(Mutex::new(file))
+                .with_filter(filter_fn(not_trace_only))
+        }))
+        .with(otlp)
+        .init();
+
+    if let Some(e) = file_error {
+        warn!("Failed to open log file, logging to console only: {}", e);
+    }
+
+    if let Some(e) = otlp_error {
+        warn!("Not exporting traces: {}", e);
+    }
+}
+
+fn not_trace_only(metadata: &Metadata) -> bool {
+    metadata.target() != telemetry::TARGET
+}
+
+/// Span fields are formatted once per field formatter type and cached, so
+/// the file layer needs its own type to avoid inheriting the console's ANSI
+/// escapes.
+#[derive(Default)]
+struct PlainFields(DefaultFields);
+
+impl<'writer> FormatFields<'writer> for PlainFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        writer: Writer<'writer>,
+        fields: R,
+    ) -> std::fmt::Result {
+        self.0.format_fields(writer, fields)
+    }
+}
+
+struct RotatingFile {
+    config: LogFile,
+    file: File,
+    size: u64,
+    day: u64,
+}
+
+impl RotatingFile {
+    fn open(config: &LogFile) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            config: config.clone(),
+            file,
+            size,
+            day: current_day(),
+        })
+    }
+
+    fn needs_rotation(&self, incoming: usize) -> bool {
+        let too_big = self
+            .config
+            .max_bytes
+            .is_some_and(|max| self.size > 0 && self.size + incoming as u64 > max);
+        let new_day = self.config.daily && self.day != current_day();
+
+        too_big || new_day
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.config.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.config.keep == 0 {
+            fs::remove_file(&self.config.path)?;
+        } else {
+            for index in (1..self.config.keep).rev() {
+                let from = self.rotated_path(index);
+
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+
+            fs::rename(&self.config.path, self.rotated_path(1))?;
+        }
+
+        *self = Self::open(&self.config)?;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.needs_rotation(buf.len()) {
+            // Keep logging to the current file rather than losing lines.
+            let _ = self.rotate();
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn current_day() -> u64 {
+    clock::unix_now() / (24 * 60 * 60)
+}
@@ -1,52 +1,319 @@
-use reqwest::{
-    blocking::Client,
-    header::{HeaderMap, HeaderValue},
+mod audit;
+mod aws;
+mod breaker;
+mod browser;
+mod captcha;
+mod cli;
+mod client;
+mod clock;
+mod community;
+mod config_file;
+mod cookies;
+mod daemon;
+mod device;
+mod discovery;
+mod ds;
+mod dump;
+mod error_reports;
+mod events;
+mod features;
+mod games;
+mod gha;
+mod healthcheck;
+mod lambda;
+mod lock;
+mod logging;
+mod metrics;
+mod mqtt;
+mod notes;
+mod notify;
+mod progress;
+mod record;
+mod redeem;
+mod refresh;
+mod reminders;
+mod report;
+mod resign;
+mod retcode;
+mod rewards;
+mod server;
+mod setup;
+mod state;
+mod stats;
+mod stoken;
+mod systemd;
+mod telemetry;
+mod validate;
+mod vault;
+
+use breaker::CircuitBreaker;
+use captcha::{CaptchaSolver, CaptchaToken, Solution};
+use cli::{Args, Command, Delay, HumanDuration, Mode, OutputFormat};
+use client::{Backend, HttpClient, Impersonate, Request, Response};
+use daemon::{ConfigWatch, DaemonConfig, Shutdown, Wake};
+use device::Device;
+use error_reports::SentryConfig;
+use games::{Game, GameOverrides, GAMES};
+use healthcheck::Healthcheck;
+use http::{
+    header::{HeaderMap, HeaderName, HeaderValue, COOKIE, RETRY_AFTER, SET_COOKIE},
+    Method, StatusCode,
 };
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs};
-
-pub struct Game<'a> {
-    name: &'a str,
-    act_id: &'a str,
-    url_get_status: &'a str,
-    url_sign: &'a str,
-    rpc_sign_game: Option<&'a str>,
-}
-
-const GAMES: &[Game] = &[
-    Game {
-        name: "Genshin Impact",
-        act_id: "e202102251931481",
-        url_get_status: "https://sg-hk4e-api.hoyolab.com/event/sol/info",
-        url_sign: "https://sg-hk4e-api.hoyolab.com/event/sol/sign",
-        rpc_sign_game: None,
-    },
-    Game {
-        name: "Honkai Star Rail",
-        act_id: "e202303301540311",
-        url_get_status: "https://sg-public-api.hoyolab.com/event/luna/os/info",
-        url_sign: "https://sg-public-api.hoyolab.com/event/luna/os/sign",
-        rpc_sign_game: None,
-    },
-    Game {
-        name: "Zenless Zone Zero",
-        act_id: "e202406031448091",
-        url_get_status: "https://sg-public-api.hoyolab.com/event/luna/zzz/os/info",
-        url_sign: "https://sg-public-api.hoyolab.com/event/luna/zzz/os/sign",
-        rpc_sign_game: Some("zzz"),
-    },
-];
+use logging::LogFile;
+use metrics::{Metrics, TimedClient};
+use mqtt::Mqtt;
+use notify::{Notification, NotificationQueue, Notifier};
+use progress::Progress;
+use redeem::{GiftCodeFeed, RedeemStatus};
+use refresh::CookieRefresh;
+use report::{GameResult, RewardKind, Status};
+use reqwest::{blocking::Client, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use server::{Control, ServerConfig, Trigger};
+use state::{LastRun, Notified, StateStore};
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    io::{self, IsTerminal},
+    iter, mem,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    rc::Rc,
+    slice,
+    sync::Arc,
+    time::Duration,
+};
+use telemetry::OtlpConfig;
+use tracing::{debug, error, field, info, info_span, trace, warn, Instrument};
+use validate::CookieStatus;
+use vault::VaultConfig;
 
 #[derive(Deserialize)]
 pub struct Config {
     accounts: Vec<Account>,
-    healthcheck: Option<String>,
+    healthcheck: Option<Healthcheck>,
+    #[serde(default)]
+    notifiers: Vec<Notifier>,
+    /// Only notifies about an account when one of its games fared differently
+    /// than in the last notification about it, e.g. not every day while its
+    /// cookies stay expired. Needs `state_file`.
+    #[serde(default)]
+    notify_on_change: bool,
+    /// Sends a notification per account instead of one for the whole run,
+    /// e.g. when every account's owner watches the same webhook for theirs.
+    #[serde(default)]
+    notify_per_account: bool,
+    log_file: Option<LogFile>,
+    /// Publishes every run's results for Home Assistant.
+    mqtt: Option<Mqtt>,
+    otlp: Option<OtlpConfig>,
+    sentry: Option<SentryConfig>,
+    /// Where the accounts' `vault_path` secrets are read from.
+    vault: Option<VaultConfig>,
+    /// Where to persist data between runs. Without it every run is stateless.
+    state_file: Option<PathBuf>,
+    /// Written after every run, for Prometheus' node exporter textfile
+    /// collector (e.g. `/var/lib/node_exporter/hoyo-checkin.prom`).
+    metrics_file: Option<PathBuf>,
+    /// Every HoYoLAB request is appended to this file as a JSON line, with
+    /// its response's status and retcode. Cookies are redacted.
+    audit_log: Option<PathBuf>,
+    #[serde(default)]
+    daemon: DaemonConfig,
+    /// HTTP endpoints served while running in daemon mode.
+    server: Option<ServerConfig>,
+    /// Solves Geetest challenges so the sign can be retried automatically.
+    captcha_solver: Option<CaptchaSolver>,
+    /// How requests introduce themselves to HoYoLAB.
+    #[serde(default)]
+    identity: Identity,
+    /// Extra headers for every HoYoLAB request, replacing any the request
+    /// would otherwise carry, e.g. a new `x-rpc-*` header the API starts to
+    /// require. An empty value removes the header.
+    #[serde(default, deserialize_with = "header_map")]
+    headers: HeaderMap,
+    /// Language of reward names and API messages, e.g. `de-de` or `ja-jp`.
+    /// `en-us` by default.
+    lang: Option<String>,
+    /// Waited before every HoYoLAB request, e.g. `2s` or `1s-5s`. Many
+    /// accounts checking in from one IP at full speed tend to be rate
+    /// limited or shown captchas.
+    request_delay: Option<Delay>,
+    /// The longest to wait in total for a request HoYoLAB rate limits with a
+    /// `Retry-After`, e.g. `5m`. 1 minute by default, `0` to never wait.
+    max_retry_after: Option<HumanDuration>,
+    /// Network failures in a row, across accounts, after which a game is
+    /// skipped for the rest of the run. 3 by default, `0` to never skip.
+    circuit_breaker: Option<u32>,
+    /// Sends HoYoLAB requests with Chrome's TLS and HTTP/2 fingerprint, for
+    /// when captchas or rejections keep coming. Needs the `impersonate`
+    /// feature.
+    #[serde(default)]
+    impersonate: bool,
+    /// Sends HoYoLAB API requests to this origin instead, e.g. a mock server.
+    base_url: Option<String>,
+    /// Looks up each game's current act_id from HoYoLAB at startup instead of
+    /// relying on the built-in one, which `games` overrides still take
+    /// precedence over.
+    #[serde(default)]
+    discover_act_ids: bool,
+    /// Only checks accounts in on the games their HoYoLAB game record shows
+    /// characters in. An account's `games` take precedence.
+    #[serde(default)]
+    detect_games: bool,
+    /// Replaced act_ids and endpoints, by game id.
+    #[serde(default)]
+    games: HashMap<String, GameOverrides>,
+    /// Codes for the `redeem` command, by game id.
+    #[serde(default)]
+    gift_codes: HashMap<String, Vec<String>>,
+    /// Fraction of a Real-Time Notes meter's cap from which the `notes`
+    /// command notifies, 0.9 by default.
+    notes_threshold: Option<f64>,
+    /// Redeems new codes from this feed on every run. Needs `state_file` to
+    /// remember which codes were already redeemed.
+    gift_code_feed: Option<GiftCodeFeed>,
 }
 
 #[derive(Deserialize)]
 pub struct Account {
     name: String,
+    #[serde(default)]
     cookies: HashMap<String, String>,
+    /// An environment variable holding the cookies as a `Cookie` header
+    /// (`ltuid_v2=...; ltoken_v2=...`), for secrets kept out of the config.
+    cookies_env: Option<String>,
+    /// A shell command printing the cookies as a `Cookie` header, to keep
+    /// them in a password manager, e.g. `op read op://vault/hoyolab/alt1` or
+    /// `pass show hoyolab/alt1`. Run every time the config is loaded.
+    cookies_cmd: Option<String>,
+    /// A secret in the config's `vault` holding a cookie per key, e.g.
+    /// `hoyolab/alt1`.
+    vault_path: Option<String>,
+    /// A Netscape `cookies.txt` export to take the HoYoLAB cookies from, as
+    /// browser extensions write them. `cookies` take precedence, since that
+    /// is where refreshed cookies are saved, then `cookies_env`,
+    /// `cookies_cmd` and `vault_path` in that order.
+    cookie_file: Option<PathBuf>,
+    /// Further cookie sets to fall back on in order once the current ones
+    /// expire, e.g. `cookie_token_v2` and `account_id_v2` when the
+    /// `ltoken_v2` stops working. Only for the run; the config keeps its
+    /// `cookies`.
+    #[serde(default)]
+    fallback_cookies: Vec<HashMap<String, String>>,
+    /// Disabled accounts are skipped unless picked with `--account`, e.g.
+    /// while their cookies are known to be expired.
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    /// Free-form labels to select groups of accounts with `--tag`.
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Canary accounts run before all others. If one of them hits a response
+    /// that looks like an API change, the remaining accounts are skipped.
+    #[serde(default)]
+    canary: bool,
+    /// Only check this account in on these games, by id or name.
+    games: Option<Vec<String>>,
+    /// Overrides the config's `lang` for this account.
+    lang: Option<String>,
+    /// Overrides the config's `request_delay` for this account.
+    request_delay: Option<Delay>,
+    /// Merged over the config's `headers` for this account.
+    #[serde(default, deserialize_with = "header_map")]
+    headers: HeaderMap,
+    /// Include character birthday reminders in this account's notification.
+    #[serde(default)]
+    reminders: bool,
+    /// Also do the HoYoLAB community's daily missions (sign in, read, like
+    /// and share posts).
+    #[serde(default)]
+    community: bool,
+    /// Spend HoYoLAB coins to make up days missed this month, once today's
+    /// check-in is done.
+    #[serde(default)]
+    makeup_sign: bool,
+    /// A hand-solved captcha to retry the sign with when no solver is configured.
+    captcha: Option<CaptchaToken>,
+    /// Long-lived HoYoLAB app token, used to renew the web cookies when they
+    /// expire. Never sent along with check-in requests.
+    stoken: Option<String>,
+    /// Account id that goes with a v2 `stoken`.
+    mid: Option<String>,
+    /// The account owner's own channel. Only gets the alerts they have to act
+    /// on (expired cookies, captchas); the full summary goes to `notifiers`.
+    contact: Option<Notifier>,
+    /// UID to redeem gift codes on, by game id, for accounts with characters
+    /// on several servers. Defaults to the highest-level character.
+    #[serde(default)]
+    uids: HashMap<String, String>,
+    /// Attached from the state file at startup, see `attach_devices`.
+    #[serde(skip)]
+    device: Option<Device>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Account {
+    /// Whether the account takes part given the `--account` and `--tag`
+    /// filters.
+    fn selected(&self, accounts: &[String], tags: &[String]) -> bool {
+        let picked = if accounts.is_empty() {
+            self.enabled
+        } else {
+            cli::matches_filter(accounts, &self.name)
+        };
+
+        picked && (tags.is_empty() || self.tags.iter().any(|tag| cli::matches_filter(tags, tag)))
+    }
+}
+
+/// Client headers sent with every request. HoYoLAB occasionally starts
+/// rejecting old app versions, which can be bumped here.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Identity {
+    #[serde(deserialize_with = "header_value")]
+    app_version: HeaderValue,
+    #[serde(deserialize_with = "header_value")]
+    client_type: HeaderValue,
+    #[serde(deserialize_with = "header_value")]
+    user_agent: HeaderValue,
+}
+
+impl Default for Identity {
+    fn default() -> Self {
+        Self {
+            app_version: HeaderValue::from_static("2.34.1"),
+            client_type: HeaderValue::from_static("4"),
+            user_agent: HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Safari/537.36"),
+        }
+    }
+}
+
+fn header_value<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HeaderValue, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    HeaderValue::from_str(&value)
+        .map_err(|_| serde::de::Error::custom(format!("invalid header value {:?}", value)))
+}
+
+fn header_map<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<HeaderMap, D::Error> {
+    HashMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, value)| {
+            let name = HeaderName::try_from(&name)
+                .map_err(|_| serde::de::Error::custom(format!("invalid header name {:?}", name)))?;
+            let value = HeaderValue::from_str(&value).map_err(|_| {
+                serde::de::Error::custom(format!("invalid header value {:?}", value))
+            })?;
+
+            Ok((name, value))
+        })
+        .collect()
 }
 
 #[derive(Serialize)]
@@ -54,142 +321,569 @@ pub struct SignRequest {
     act_id: String,
 }
 
+#[derive(Deserialize, Default)]
+pub struct SignInfo {
+    #[serde(default)]
+    is_sign: bool,
+    /// Days signed this month, today included.
+    #[serde(default)]
+    total_sign_day: u32,
+}
+
 #[derive(Deserialize)]
 pub struct SignData {
-    is_sign: Option<bool>,
+    /// Where the challenge is depends on the game, see `Game::sign_risk`.
+    #[serde(flatten)]
+    risk: GeetestRisk,
+    gt_result: Option<GeetestRisk>,
+}
+
+/// Returned with retcode 0 when HoYoLAB gates the sign behind a Geetest captcha.
+#[derive(Deserialize)]
+pub struct GeetestRisk {
+    #[serde(default)]
+    is_risk: bool,
+    risk_code: Option<i32>,
+    gt: Option<String>,
+    challenge: Option<String>,
+}
+
+impl GeetestRisk {
+    fn captcha(self) -> Option<CheckinError> {
+        (self.is_risk || self.risk_code.is_some_and(|code| code != 0)).then_some(
+            CheckinError::CaptchaRequired {
+                gt: self.gt,
+                challenge: self.challenge,
+            },
+        )
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExtraAwardData {
+    /// Whether a limited-time bonus sign-in phase is currently running.
+    #[serde(default)]
+    has_short_act: bool,
+    /// Bonus days claimed so far in the current phase.
+    #[serde(default)]
+    short_sign_day: u32,
+    #[serde(default)]
+    total_cnt: u32,
 }
 
 #[derive(Deserialize)]
-pub struct SignResponse {
+pub struct SignResponse<T = SignData> {
     retcode: Option<i32>,
     message: Option<String>,
-    data: Option<SignData>,
+    data: Option<T>,
+}
+
+/// Why a check-in for a single game did not succeed.
+#[derive(Debug)]
+enum CheckinError {
+    /// No usable response was received (connection failures, HTTP 5xx, 429).
+    Network(client::Error),
+    /// The response did not have the expected shape, usually because HoYoLAB
+    /// changed the API.
+    UnexpectedResponse(String),
+    /// The API answered with a non-zero return code.
+    Api {
+        retcode: i32,
+        message: Option<String>,
+    },
+    /// HoYoLAB wants a Geetest captcha solved before it will sign.
+    CaptchaRequired {
+        gt: Option<String>,
+        challenge: Option<String>,
+    },
+    /// The cookies are expired or were logged out, so nothing works until
+    /// they are refreshed.
+    CookieExpired { retcode: i32 },
+    /// Signing reported success but the reward still shows as unclaimed.
+    Unclaimed,
+    /// Not attempted, as the game's endpoints kept failing for other accounts.
+    EndpointDown,
+}
+
+impl CheckinError {
+    fn api(retcode: i32, message: Option<String>) -> Self {
+        match retcode {
+            retcode::ABNORMAL_REQUEST => Self::CaptchaRequired {
+                gt: None,
+                challenge: None,
+            },
+            retcode::NOT_LOGGED_IN | retcode::NOT_LOGGED_IN_RECORD => {
+                Self::CookieExpired { retcode }
+            }
+            _ => Self::Api { retcode, message },
+        }
+    }
+
+    fn is_api_change(&self) -> bool {
+        matches!(self, Self::UnexpectedResponse(_))
+    }
+
+    /// Exit code for commands that stop at a single error.
+    fn exit_code(&self) -> u8 {
+        match self {
+            Self::CookieExpired { .. } => EXIT_CONFIG,
+            Self::Network(_) | Self::EndpointDown => EXIT_NETWORK,
+            _ => EXIT_API,
+        }
+    }
+
+    fn retcode(&self) -> Option<i32> {
+        match self {
+            Self::Api { retcode, .. } | Self::CookieExpired { retcode } => Some(*retcode),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CheckinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(e) => write!(f, "{}", e),
+            Self::UnexpectedResponse(e) => write!(f, "Unexpected response: {}", e),
+            Self::Api {
+                retcode: code,
+                message,
+            } => match (retcode::meaning(*code), message) {
+                (Some(meaning), _) => write!(f, "{} ({})", meaning, code),
+                (None, Some(message)) => write!(f, "{}", message),
+                (None, None) => write!(f, "Return code is {}", code),
+            },
+            Self::CaptchaRequired { .. } => write!(f, "Captcha required"),
+            Self::CookieExpired { .. } => write!(f, "Cookies expired or logged out"),
+            Self::Unclaimed => write!(f, "Unable to claim check-in rewards"),
+            Self::EndpointDown => write!(f, "Skipped, endpoint down"),
+        }
+    }
+}
+
+impl std::error::Error for CheckinError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
 }
 
-struct HoyolabCheckin<'a> {
+struct HoyolabCheckin<'a, C> {
+    config: &'a Config,
     account: &'a Account,
-    client: &'a Client,
-    games: &'a [Game<'a>],
+    client: &'a C,
+    games: &'a [&'a dyn Game],
+    /// Only query the status endpoints, never sign.
+    dry_run: bool,
+    /// Whether someone is at the terminal to solve a captcha by hand.
+    interactive: bool,
 }
 
-impl<'a> HoyolabCheckin<'a> {
-    fn new(account: &'a Account, client: &'a Client, games: &'a [Game]) -> Self {
+impl<'a, C: HttpClient> HoyolabCheckin<'a, C> {
+    fn new(
+        config: &'a Config,
+        account: &'a Account,
+        client: &'a C,
+        games: &'a [&'a dyn Game],
+        dry_run: bool,
+        interactive: bool,
+    ) -> Self {
         Self {
+            config,
             account,
             client,
             games,
+            dry_run,
+            interactive,
         }
     }
 
-    fn get_status(&self, game: &Game) -> Result<bool, String> {
-        let request = self
-            .client
-            .get(game.url_get_status)
-            .query(&[("lang", "en-us"), ("act_id", &game.act_id)])
-            .headers(self.build_headers(game));
-        let response: SignResponse = request
-            .send()
-            .map_err(|e| e.to_string())?
-            .json()
-            .map_err(|e| e.to_string())?;
+    /// HoYoLAB language code to ask for, such as `en-us`.
+    fn lang(&self) -> &str {
+        self.account
+            .lang
+            .as_deref()
+            .or(self.config.lang.as_deref())
+            .unwrap_or("en-us")
+    }
+
+    /// Builds a request to a HoYoLAB endpoint carrying the game's headers.
+    fn request(
+        &self,
+        game: &dyn Game,
+        method: Method,
+        url: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<Request, CheckinError> {
+        let url = match &self.config.base_url {
+            Some(base) => {
+                let path = Url::parse(url).map_err(|e| CheckinError::Network(e.into()))?;
+                format!("{}{}", base.trim_end_matches('/'), path.path())
+            }
+            None => url.to_string(),
+        };
+        let url =
+            Url::parse_with_params(&url, query).map_err(|e| CheckinError::Network(e.into()))?;
+        let mut request = http::Request::builder()
+            .method(method)
+            .uri(url.as_str())
+            .body(body)
+            .map_err(|e| CheckinError::Network(e.into()))?;
+
+        *request.headers_mut() = self.build_headers(game);
+        Ok(request)
+    }
+
+    /// Sends a request and decodes the response. A rate limited request is
+    /// sent again once HoYoLAB's `Retry-After` has passed, as long as the
+    /// waits add up to no more than `max_retry_after`.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        mut request: Request,
+    ) -> Result<SignResponse<T>, CheckinError> {
+        if let Some(delay) = self.account.request_delay.or(self.config.request_delay) {
+            self.client.sleep(delay.pick()).await;
+        }
+
+        let max_wait = self
+            .config
+            .max_retry_after
+            .map_or(MAX_RETRY_AFTER, |max| max.0);
+        let mut waited = Duration::ZERO;
+
+        loop {
+            debug!("{} {}", request.method(), request.uri());
+            events::emit(
+                "request_sent",
+                json!({ "method": request.method().as_str(), "url": request.uri().to_string() }),
+            );
+            trace!(
+                "Request headers: {}",
+                redact_headers(request.headers()).join(", ")
+            );
+
+            let span = info_span!(
+                target: telemetry::TARGET,
+                "request",
+                method = %request.method(),
+                url = %request.uri(),
+                status = field::Empty,
+            );
+            let dumped = dump::request(&request);
+            let audit = audit::start(&self.account.name, &request);
+            let retry = client::copy_request(&request);
+            let response = self.client.execute(request).instrument(span.clone()).await;
+
+            if let Some(audit) = audit {
+                audit.finish(response.as_ref());
+            }
+
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => {
+                    if let Some(dumped) = &dumped {
+                        dump::write(dumped, None, &e.to_string());
+                    }
+                    return Err(CheckinError::Network(e));
+                }
+            };
+            span.record("status", response.status().as_u16());
+
+            if let Some(wait) = retry_after(&response).filter(|wait| waited + *wait <= max_wait) {
+                warn!(
+                    "Rate limited by HoYoLAB, retrying in {} seconds",
+                    wait.as_secs()
+                );
+                self.client.sleep(wait).await;
+                waited += wait;
+                request = retry;
+                continue;
+            }
+
+            let decoded = decode(&response);
+
+            if let (Some(dumped), Err(e)) = (&dumped, &decoded) {
+                dump::write(dumped, Some(&response), &e.to_string());
+            }
+
+            return decoded;
+        }
+    }
+
+    async fn info(&self, game: &dyn Game) -> Result<SignInfo, CheckinError> {
+        let request = self.request(
+            game,
+            Method::GET,
+            game.url_get_status(),
+            &[("lang", self.lang()), ("act_id", game.act_id())],
+            Vec::new(),
+        )?;
+        let response: SignResponse<SignInfo> = self.send(request).await?;
 
         let return_code = response.retcode.unwrap_or(0);
 
         if return_code != 0 {
-            return Err(response
-                .message
-                .unwrap_or_else(|| format!("Return code is {}", return_code).to_string()));
+            return Err(CheckinError::api(return_code, response.message));
         }
 
-        Ok(response
-            .data
-            .map_or(false, |data| data.is_sign.unwrap_or(false)))
+        Ok(response.data.unwrap_or_default())
     }
 
-    fn sign(&self, game: &Game) -> Result<(), String> {
-        let data = serde_json::to_string(&SignRequest {
-            act_id: game.act_id.to_string(),
+    async fn sign(&self, game: &dyn Game, solution: Option<&Solution>) -> Result<(), CheckinError> {
+        let data = serde_json::to_vec(&SignRequest {
+            act_id: game.act_id().to_string(),
         })
-        .map_err(|e| e.to_string())?;
-
-        let request = self
-            .client
-            .post(game.url_sign)
-            .query(&[("lang", "en-us")])
-            .headers(self.build_headers(game))
-            .body(data);
-        let response: SignResponse = request
-            .send()
-            .map_err(|e| e.to_string())?
-            .json()
-            .map_err(|e| e.to_string())?;
+        .map_err(|e| CheckinError::UnexpectedResponse(e.to_string()))?;
+
+        let mut request = self.request(
+            game,
+            Method::POST,
+            game.url_sign(),
+            &[("lang", self.lang())],
+            data,
+        )?;
+
+        if let Some(solution) = solution {
+            for (name, value) in [
+                ("x-rpc-challenge", &solution.challenge),
+                ("x-rpc-validate", &solution.validate),
+                ("x-rpc-seccode", &solution.seccode),
+            ] {
+                request.headers_mut().insert(
+                    name,
+                    HeaderValue::from_str(value)
+                        .map_err(|e| CheckinError::UnexpectedResponse(e.to_string()))?,
+                );
+            }
+        }
+
+        let response: SignResponse = self.send(request).await?;
 
         let return_code = response.retcode.unwrap_or(0);
 
-        if return_code == -5003 {
-            // Traveler, you've already checked in today~
+        if return_code == retcode::ALREADY_SIGNED {
             return Ok(());
         }
 
         if return_code != 0 {
-            return Err(response
-                .message
-                .unwrap_or_else(|| format!("Return code is {}", return_code).to_string()));
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        if let Some(captcha) = response
+            .data
+            .and_then(|data| game.sign_risk(data))
+            .and_then(GeetestRisk::captcha)
+        {
+            return Err(captcha);
         }
 
         Ok(())
     }
 
-    fn process_game(&self, game: &Game) -> bool {
-        match self.get_status(game) {
-            Ok(false) => {
-                if let Err(e) = self.sign(game) {
-                    println!(
-                        "Failed to sign in for {} on {}: {}",
-                        self.account.name, game.name, e
-                    );
-                    return false;
+    /// Signs, retrying once if HoYoLAB asks for a captcha. The solution
+    /// comes from the configured solver, the account's hand-solved token or
+    /// the person at the terminal, in that order.
+    async fn sign_solving_captcha(&self, game: &dyn Game) -> Result<(), CheckinError> {
+        let (gt, challenge) = match self.sign(game, None).await {
+            Err(CheckinError::CaptchaRequired { gt, challenge }) => (gt, challenge),
+            result => return result,
+        };
+
+        let solution = if let (Some(solver), Some(gt), Some(challenge)) =
+            (self.config.captcha_solver.as_ref(), &gt, &challenge)
+        {
+            info!(
+                "Captcha required, submitting it to {}",
+                solver.provider.name()
+            );
+            solver.solve(self.client, gt, challenge).await
+        } else if let Some(token) = &self.account.captcha {
+            info!("Captcha required, retrying with the solved token from config");
+            Ok(token.solution())
+        } else if let (true, Some(gt), Some(challenge)) = (self.interactive, &gt, &challenge) {
+            captcha::solve_manually(gt, challenge)
+        } else {
+            Err(format!(
+                "no solver configured, check in manually at {}",
+                game.url_page()
+            ))
+        };
+
+        match solution {
+            Ok(solution) => self.sign(game, Some(&solution)).await,
+            Err(e) => {
+                warn!("Failed to solve captcha: {}", e);
+                Err(CheckinError::CaptchaRequired { gt, challenge })
+            }
+        }
+    }
+
+    /// Claims the daily reward. Also returns the days checked in this month,
+    /// which for a dry run do not include today yet.
+    async fn claim(&self, game: &dyn Game) -> Result<(Status, u32), CheckinError> {
+        match self.info(game).await {
+            Ok(info) if !info.is_sign && self.dry_run => {
+                info!("Dry run: daily reward is unclaimed and would be claimed");
+                Ok((Status::WouldClaim, info.total_sign_day))
+            }
+            Ok(info) if !info.is_sign => {
+                if let Err(e) = self.sign_solving_captcha(game).await {
+                    error!("Failed to sign in: {}", e);
+                    return Err(e);
                 }
 
-                if let Ok(true) = self.get_status(game) {
-                    println!(
-                        "Daily check-in successful for {} on {}!",
-                        self.account.name, game.name
-                    );
-                    return true;
+                match self.info(game).await {
+                    Ok(info) if info.is_sign => {
+                        info!("Daily check-in successful!");
+                        Ok((Status::Claimed, info.total_sign_day))
+                    }
+                    _ => {
+                        error!("Unable to claim check-in rewards");
+                        Err(CheckinError::Unclaimed)
+                    }
                 }
+            }
+            Ok(info) => {
+                info!("Daily check-in already done!");
+                Ok((Status::AlreadyClaimed, info.total_sign_day))
+            }
+            Err(e) => {
+                error!("Failed check-in: {}", e);
+                Err(e)
+            }
+        }
+    }
 
-                println!(
-                    "ERROR: Unable to claim check-in rewards for {} on {}",
-                    self.account.name, game.name
+    /// Claims the event bonus if a bonus phase is running. Returns `None`
+    /// when there is nothing to claim.
+    async fn claim_extra_award(
+        &self,
+        game: &dyn Game,
+        url: &str,
+    ) -> Result<Option<Status>, CheckinError> {
+        let request = self.request(
+            game,
+            Method::GET,
+            url,
+            &[("lang", self.lang()), ("act_id", game.act_id())],
+            Vec::new(),
+        )?;
+        // The bonus is optional, so failing to look it up must not fail the run.
+        let info = match self.send::<ExtraAwardData>(request).await {
+            Ok(response) if response.retcode.unwrap_or(0) == 0 => response.data,
+            Ok(response) => {
+                warn!(
+                    "Failed to check for event bonus: {}",
+                    CheckinError::api(response.retcode.unwrap_or(0), response.message)
                 );
+                None
             }
-            Ok(true) => println!(
-                "Daily check-in already done for {} on {}!",
-                self.account.name, game.name
-            ),
-            Err(e) => println!(
-                "Failed check-in for {} on {}: {}",
-                self.account.name, game.name, e
-            ),
+            Err(e) => {
+                warn!("Failed to check for event bonus: {}", e);
+                None
+            }
+        };
+
+        let Some(info) = info.filter(|data| data.has_short_act) else {
+            debug!("No event bonus active");
+            return Ok(None);
+        };
+
+        if self.dry_run {
+            info!(
+                "Dry run: event bonus is active ({}/{} days) and would be claimed",
+                info.short_sign_day, info.total_cnt
+            );
+            return Ok(Some(Status::WouldClaim));
         }
 
-        false
+        let data = serde_json::to_vec(&SignRequest {
+            act_id: game.act_id().to_string(),
+        })
+        .map_err(|e| CheckinError::UnexpectedResponse(e.to_string()))?;
+
+        let request = self.request(game, Method::POST, url, &[("lang", self.lang())], data)?;
+        let response: SignResponse<ExtraAwardData> = self.send(request).await?;
+
+        match response.retcode.unwrap_or(0) {
+            0 => {
+                info!(
+                    "Event bonus claimed ({}/{} days)",
+                    info.short_sign_day + 1,
+                    info.total_cnt
+                );
+                Ok(Some(Status::Claimed))
+            }
+            retcode::ALREADY_SIGNED => {
+                info!("Event bonus already claimed!");
+                Ok(Some(Status::AlreadyClaimed))
+            }
+            retcode => {
+                let e = CheckinError::api(retcode, response.message);
+                error!("Failed to claim event bonus: {}", e);
+                Err(e)
+            }
+        }
     }
 
-    fn process(&self) -> bool {
-        let mut success = true;
+    async fn process_game(&self, game: &dyn Game) -> Vec<GameResult> {
+        let started_at = clock::unix_now();
+        let started_ms = clock::unix_millis();
+        let span = info_span!(
+            "game",
+            game = %game.name(),
+            server_date = %clock::server_date(started_at)
+        );
+
+        async {
+            let (outcome, sign_day) = match self.claim(game).await {
+                Ok((status, sign_day)) => (Ok(status), Some(sign_day)),
+                Err(e) => (Err(e), None),
+            };
+            let reward = match outcome {
+                Ok(Status::Claimed) => self.claimed_reward(game).await,
+                _ => None,
+            };
+            let mut results = vec![
+                GameResult::new(&self.account.name, game, started_at, outcome)
+                    .reward(reward)
+                    .sign_day(sign_day)
+                    .timed(started_ms),
+            ];
+
+            if let Some(url) = game.url_extra_award() {
+                let started_at = clock::unix_now();
+                let started_ms = clock::unix_millis();
 
-        for game in self.games {
-            if !self.process_game(game) {
-                success = false;
+                if let Some(outcome) = self.claim_extra_award(game, url).await.transpose() {
+                    results.push(
+                        GameResult::new(&self.account.name, game, started_at, outcome)
+                            .extra()
+                            .timed(started_ms),
+                    );
+                }
             }
+
+            results
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn process(&self) -> Vec<GameResult> {
+        let mut results = Vec::new();
+
+        for &game in self.games {
+            results.extend(self.process_game(game).await);
         }
 
-        success
+        results
     }
 
-    fn build_headers(&self, game: &Game) -> HeaderMap {
+    fn build_headers(&self, game: &dyn Game) -> HeaderMap {
         let mut headers = HeaderMap::new();
 
         headers.insert(
@@ -198,7 +892,8 @@ impl<'a> HoyolabCheckin<'a> {
         );
         headers.insert(
             "Accept-Language",
-            HeaderValue::from_static("en-US,en;q=0.5"),
+            HeaderValue::from_str(&format!("{},en;q=0.5", self.lang()))
+                .unwrap_or(HeaderValue::from_static("en-US,en;q=0.5")),
         );
         headers.insert(
             "Origin",
@@ -212,16 +907,28 @@ impl<'a> HoyolabCheckin<'a> {
             "Content-Type",
             HeaderValue::from_static("application/json;charset=utf-8"),
         );
-        headers.insert("User-Agent", HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Safari/537.36"));
-        headers.insert("x-rpc-app_version", HeaderValue::from_static("2.34.1"));
-        headers.insert("x-rpc-client_type", HeaderValue::from_static("4"));
-
-        if let Some(rpc_sign_game) = &game.rpc_sign_game {
-            headers.insert(
-                "x-rpc-signgame",
-                HeaderValue::from_str(rpc_sign_game)
-                    .expect("Failed to build x-rpc-signgame header"),
-            );
+        headers.insert("User-Agent", self.config.identity.user_agent.clone());
+        headers.insert(
+            "x-rpc-app_version",
+            self.config.identity.app_version.clone(),
+        );
+        headers.insert(
+            "x-rpc-client_type",
+            self.config.identity.client_type.clone(),
+        );
+
+        game.headers(&mut headers);
+
+        if let Some(device) = &self.account.device {
+            device.headers(&mut headers);
+        }
+
+        for (name, value) in self.config.headers.iter().chain(&self.account.headers) {
+            if value.is_empty() {
+                headers.remove(name);
+            } else {
+                headers.insert(name, value.clone());
+            }
         }
 
         headers.insert(
@@ -235,35 +942,2066 @@ impl<'a> HoyolabCheckin<'a> {
                     .collect::<Vec<_>>()
                     .join("; "),
             )
-            .expect("Failed to build cookie header"),
+            .expect("Cookies are checked when the config is loaded"),
         );
 
         headers
     }
 }
 
-fn main() {
-    let data = fs::read_to_string("config.json").expect("Failed to read config.json");
-    let config: Config = serde_json::from_str(&data).expect("Invalid JSON");
+/// Default of the config's `max_retry_after`.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
 
-    let mut success = true;
-    let client = Client::new();
+/// How long a rate limited response asks to wait before trying again. Only
+/// the delay-seconds form of `Retry-After` is understood.
+fn retry_after(response: &Response) -> Option<Duration> {
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Turns a HoYoLAB response into its JSON envelope. Rate limits and server
+/// errors count as network errors, since they usually pass.
+fn decode<T: DeserializeOwned>(response: &Response) -> Result<SignResponse<T>, CheckinError> {
+    let status = response.status();
+
+    if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+        return Err(CheckinError::UnexpectedResponse(format!("HTTP {}", status)));
+    }
+
+    if status.is_client_error() || status.is_server_error() {
+        return Err(CheckinError::Network(format!("HTTP {}", status).into()));
+    }
+
+    serde_json::from_slice(response.body())
+        .map_err(|e| CheckinError::UnexpectedResponse(e.to_string()))
+}
+
+/// Renders headers as `name: value` lines with credentials blanked out.
+fn redact_headers(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            if name == COOKIE || name == SET_COOKIE {
+                format!("{}: <redacted>", name)
+            } else {
+                format!("{}: {}", name, value.to_str().unwrap_or("<binary>"))
+            }
+        })
+        .collect()
+}
+
+/// Logs why the binary cannot start, and on Lambda reports it as the
+/// runtime's init error so the failed invocation shows the reason.
+fn startup_failed(args: &Args, code: u8, message: impl fmt::Display) -> ExitCode {
+    error!("{}", message);
+
+    if matches!(args.mode, Mode::Lambda) {
+        lambda::init_error(&message.to_string());
+    }
+
+    ExitCode::from(code)
+}
+
+/// Exit codes, so cron, systemd `OnFailure=` and CI wrappers can tell
+/// failures apart.
+const EXIT_CHECKIN_FAILED: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_CONFIG: u8 = 3;
+const EXIT_NETWORK: u8 = 4;
+const EXIT_API: u8 = 5;
+/// Another instance is running against the same state or config.
+const EXIT_LOCKED: u8 = 6;
+
+/// Expired cookies take precedence over API errors, then network errors, then
+/// anything else, since they point at the most actionable problem.
+fn exit_code(report: &[GameResult]) -> ExitCode {
+    let errors: Vec<&CheckinError> = report
+        .iter()
+        .filter_map(|result| result.error.as_ref())
+        .collect();
+
+    if errors.is_empty() {
+        ExitCode::SUCCESS
+    } else if errors
+        .iter()
+        .any(|e| matches!(e, CheckinError::CookieExpired { .. }))
+    {
+        ExitCode::from(EXIT_CONFIG)
+    } else if errors.iter().any(|e| {
+        matches!(
+            e,
+            CheckinError::Api { .. } | CheckinError::CaptchaRequired { .. }
+        )
+    }) {
+        ExitCode::from(EXIT_API)
+    } else if errors.iter().any(|e| matches!(e, CheckinError::Network(_))) {
+        ExitCode::from(EXIT_NETWORK)
+    } else {
+        ExitCode::from(EXIT_CHECKIN_FAILED)
+    }
+}
+
+/// Why the config could not be loaded. `name` says where it came from.
+#[derive(Debug)]
+enum ConfigError {
+    Read {
+        name: String,
+        error: io::Error,
+    },
+    Invalid {
+        name: String,
+        error: serde_json::Error,
+    },
+    /// Valid JSON that does not fit the config's shape, with where it does
+    /// not, e.g. `accounts[2].cookies`.
+    Schema {
+        name: String,
+        error: serde_path_to_error::Error<serde_json::Error>,
+    },
+    NoProfile {
+        name: String,
+        profile: String,
+    },
+    InvalidProfile {
+        profile: String,
+    },
+    DuplicateAccount {
+        name: String,
+        account: String,
+    },
+    Cookies {
+        account: String,
+        error: String,
+    },
+    Vault {
+        error: client::Error,
+    },
+    Aws {
+        error: client::Error,
+    },
+}
 
-    for account in config.accounts {
-        let checkin = HoyolabCheckin::new(&account, &client, GAMES);
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { name, error } => write!(f, "Failed to read {}: {}", name, error),
+            Self::Invalid { name, error } => write!(f, "Invalid {}: {}", name, error),
+            Self::Schema { name, error } => write!(f, "Invalid {}: {}", name, error),
+            Self::NoProfile { name, profile } => {
+                write!(f, "No profile named \"{}\" in {}", profile, name)
+            }
+            Self::InvalidProfile { profile } => {
+                write!(f, "Profile \"{}\" must be an object", profile)
+            }
+            Self::DuplicateAccount { name, account } => {
+                write!(
+                    f,
+                    "Account \"{}\" in {} is already configured",
+                    account, name
+                )
+            }
+            Self::Cookies { account, error } => {
+                write!(
+                    f,
+                    "Failed to load the cookies of \"{}\": {}",
+                    account, error
+                )
+            }
+            Self::Vault { error } => write!(f, "Failed to log in to Vault: {}", error),
+            Self::Aws { error } => write!(f, "Failed to read from AWS: {}", error),
+        }
+    }
+}
 
-        if !checkin.process() {
-            success = false;
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Read { error, .. } => Some(error),
+            Self::Invalid { error, .. } => Some(error),
+            Self::Schema { error, .. } => Some(error.inner()),
+            _ => None,
         }
     }
+}
 
-    if let Some(healthcheck) = config.healthcheck {
-        let url = if !success {
-            format!("{}/fail", healthcheck)
-        } else {
-            healthcheck.to_string()
+/// Loads `config.json`. A profile's keys replace the top-level keys of the
+/// same name, so a `test` profile can swap in a mock `base_url` and dummy
+/// accounts while sharing everything else. Accounts in `config.d/*.json` are
+/// added to the config's own, unless the profile replaces them.
+fn load_config(path: &Path, profile: Option<&str>) -> Result<Config, ConfigError> {
+    let (name, data) = if config_file::is_stdin(path) {
+        (
+            "config from stdin".to_string(),
+            io::read_to_string(io::stdin()),
+        )
+    } else {
+        (path.display().to_string(), fs::read_to_string(path))
+    };
+    let data = match data {
+        Ok(data) => data,
+        Err(error) => return Err(ConfigError::Read { name, error }),
+    };
+    let invalid = |error| ConfigError::Invalid {
+        name: name.clone(),
+        error,
+    };
+    let mut config: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&data).map_err(invalid)?;
+    let profiles = config.remove("profiles");
+    let mut merge_accounts = true;
+
+    if let Some(profile) = profile {
+        let overrides = profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(profile))
+            .ok_or_else(|| ConfigError::NoProfile {
+                name: name.clone(),
+                profile: profile.to_string(),
+            })?
+            .as_object()
+            .ok_or_else(|| ConfigError::InvalidProfile {
+                profile: profile.to_string(),
+            })?;
+
+        if overrides.contains_key("accounts") {
+            merge_accounts = false;
+        }
+
+        config.extend(overrides.clone());
+    }
+
+    if merge_accounts {
+        let files = config_file::account_files(path).map_err(|error| ConfigError::Read {
+            name: path.with_file_name("config.d").display().to_string(),
+            error,
+        })?;
+        let accounts = config
+            .entry("accounts")
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+
+        for file in files {
+            let name = file.display().to_string();
+            let data = match fs::read_to_string(&file) {
+                Ok(data) => data,
+                Err(error) => return Err(ConfigError::Read { name, error }),
+            };
+            let account: serde_json::Map<String, serde_json::Value> =
+                match serde_json::from_str(&data) {
+                    Ok(account) => account,
+                    Err(error) => return Err(ConfigError::Invalid { name, error }),
+                };
+
+            let account_name = account.get("name").and_then(|name| name.as_str());
+
+            if let Some(accounts) = accounts.as_array_mut() {
+                if let Some(account_name) = account_name.filter(|account_name| {
+                    accounts
+                        .iter()
+                        .any(|existing| existing["name"].as_str() == Some(account_name))
+                }) {
+                    return Err(ConfigError::DuplicateAccount {
+                        account: account_name.to_string(),
+                        name,
+                    });
+                }
+
+                accounts.push(account.into());
+            }
+        }
+    }
+
+    let mut config = serde_json::Value::from(config);
+
+    if aws::references(&config) {
+        client::block_on(aws::resolve(&Client::new(), &mut config))
+            .map_err(|error| ConfigError::Aws { error })?;
+    }
+
+    let mut config: Config =
+        serde_path_to_error::deserialize(config).map_err(|error| ConfigError::Schema {
+            name: name.clone(),
+            error,
+        })?;
+
+    let vault = config
+        .vault
+        .as_ref()
+        .filter(|_| {
+            config
+                .accounts
+                .iter()
+                .any(|account| account.vault_path.is_some())
+        })
+        .map(|vault| {
+            let client = Client::new();
+            client::block_on(vault::login(&client, vault)).map(|vault| (client, vault))
+        })
+        .transpose()
+        .map_err(|error| ConfigError::Vault { error })?;
+
+    for account in &mut config.accounts {
+        let error = |error| ConfigError::Cookies {
+            account: account.name.clone(),
+            error,
         };
+        let mut cookies = Vec::new();
+
+        if let Some(variable) = &account.cookies_env {
+            let header = env::var(variable).map_err(|e| error(format!("{}: {}", variable, e)))?;
+            cookies.extend(cookies::parse_cookie_header(&header));
+        }
+
+        if let Some(command) = &account.cookies_cmd {
+            cookies.extend(cookies::from_command(command).map_err(error)?);
+        }
+
+        if let Some(path) = &account.vault_path {
+            let (client, vault) = vault
+                .as_ref()
+                .ok_or_else(|| error(format!("No vault in the config to read {} from", path)))?;
+            let cookies_in_vault = client::block_on(vault.cookies(client, path))
+                .map_err(|e| error(format!("{}: {}", path, e)))?;
+            cookies.extend(cookies_in_vault);
+        }
 
-        let _ = client.get(&url).send();
+        if let Some(path) = &account.cookie_file {
+            cookies.extend(cookies::from_cookies_txt(path).map_err(error)?);
+        }
+
+        for (name, value) in cookies {
+            account.cookies.entry(name).or_insert(value);
+        }
+
+        // Secrets often carry the trailing newline of the file or command
+        // they came from.
+        for cookies in iter::once(&mut account.cookies).chain(&mut account.fallback_cookies) {
+            cookies
+                .values_mut()
+                .for_each(|value| *value = value.trim().to_string());
+
+            if let Some(name) = validate::unsendable(cookies) {
+                return Err(error(format!("invalid value for {} cookie", name)));
+            }
+        }
     }
+
+    Ok(config)
 }
+
+struct Runner<'a> {
+    config: Config,
+    /// Where the config was loaded from and with which profile, for
+    /// reloading it.
+    config_path: PathBuf,
+    profile: Option<String>,
+    client: &'a Client,
+    /// Accounts selected with `--account`; empty means all.
+    account_filter: Vec<String>,
+    /// Tags selected with `--tag`; empty means all.
+    tag_filter: Vec<String>,
+    /// Games selected with `--game`.
+    games: Vec<&'a dyn Game>,
+    output: OutputFormat,
+    /// Print the summary table at the end of text output.
+    summary: bool,
+    /// Color the summary table by status.
+    color: bool,
+    /// Show a progress line while several accounts check in.
+    progress: bool,
+    /// Where to write the run's results for other automation.
+    output_file: Option<PathBuf>,
+    /// Annotate the GitHub Actions workflow run with the results.
+    gha: bool,
+    /// Skip signing and every side effect: notifications, healthchecks, state.
+    dry_run: bool,
+    /// Prompt for captcha solutions on the terminal.
+    interactive: bool,
+    /// Ignore the state file's record of today's claims.
+    force: bool,
+    /// Only check in the games whose latest attempt failed.
+    retry_failed: bool,
+    /// In daemon mode, failures are only alerted on once the next reset is
+    /// closer than this.
+    grace_window: Option<Duration>,
+    /// In daemon mode, whether a failure of this run will be retried. Its
+    /// alerts are then held back, within the grace window if there is one,
+    /// so that only the last attempt alerts.
+    retry_follows: bool,
+    state: Option<StateStore>,
+    metrics: Arc<Metrics>,
+    /// `client` for HoYoLAB requests, timed into `metrics`.
+    api: TimedClient<'a>,
+    refresh: Option<Arc<CookieRefresh>>,
+    /// In Docker modes, a signal stops the run after the account in flight,
+    /// which is then reported and saved as usual.
+    shutdown: Option<Shutdown>,
+    /// In daemon mode, fed before every account so a long pass doesn't trip
+    /// systemd's watchdog.
+    watchdog: Option<Rc<systemd::Notifier>>,
+}
+
+impl<'a> Runner<'a> {
+    /// Reloads the accounts and notifiers from the config file. The rest of
+    /// the config only takes effect on restart.
+    fn reload(&mut self) -> Result<(), ConfigError> {
+        let config = load_config(&self.config_path, self.profile.as_deref())?;
+        let mut accounts = config.accounts;
+
+        for account in &mut accounts {
+            account.device = self
+                .config
+                .accounts
+                .iter()
+                .find(|old| old.name == account.name)
+                .and_then(|old| old.device.clone())
+                .or_else(|| {
+                    self.state
+                        .as_ref()
+                        .and_then(|store| store.state.devices.get(&account.name).cloned())
+                });
+        }
+
+        self.config.accounts = accounts;
+        self.config.notifiers = config.notifiers;
+        Ok(())
+    }
+
+    fn run(&mut self) -> Vec<GameResult> {
+        let _span = info_span!(target: telemetry::TARGET, "run").entered();
+        let started_at = clock::unix_now();
+        let mut report = Vec::new();
+
+        // A started run that never reports is flagged as down, which would
+        // defeat the grace window.
+        if let (Some(healthcheck), false, None, false) = (
+            &self.config.healthcheck,
+            self.dry_run,
+            self.grace_window,
+            self.retry_follows,
+        ) {
+            healthcheck.start(self.client);
+        }
+
+        if self
+            .refresh
+            .as_ref()
+            .is_some_and(|refresh| refresh.take_updated())
+        {
+            if let Err(e) = self.reload() {
+                error!("Failed to reload accounts with refreshed cookies: {}", e);
+            }
+        }
+
+        let queue = NotificationQueue::default();
+        let mut notifications = Vec::new();
+        let mut alerts = Vec::new();
+        let mut feed = HashMap::new();
+
+        if self.config.gift_code_feed.is_some() && self.state.is_none() {
+            warn!("Not redeeming codes from gift_code_feed without a state_file to track them in");
+        }
+
+        if self.config.notify_on_change && self.state.is_none() {
+            warn!("Notifying on every run, notify_on_change needs a state_file to compare with");
+        }
+
+        let mut accounts: Vec<usize> = self
+            .config
+            .accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, account)| account.selected(&self.account_filter, &self.tag_filter))
+            .map(|(index, _)| index)
+            .collect();
+        accounts.sort_by_key(|index| !self.config.accounts[*index].canary);
+        events::emit(
+            "run_started",
+            json!({ "accounts": accounts.len(), "dry_run": self.dry_run }),
+        );
+
+        let mut progress =
+            (self.progress && accounts.len() > 1).then(|| Progress::start(accounts.len()));
+        let mut breaker =
+            CircuitBreaker::new(self.config.circuit_breaker.unwrap_or(breaker::THRESHOLD));
+
+        let total = accounts.len();
+
+        for (position, index) in accounts.into_iter().enumerate() {
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.keepalive();
+            }
+
+            if self.shutdown.as_ref().is_some_and(Shutdown::requested) {
+                warn!(
+                    "Shutdown requested, skipping the remaining {} of {} accounts",
+                    total - position,
+                    total
+                );
+                break;
+            }
+
+            let _span =
+                info_span!("account", account = %self.config.accounts[index].name).entered();
+            let games = self.pending_games(index, started_at);
+
+            if let Some(progress) = &mut progress {
+                progress.account(&self.config.accounts[index].name);
+            }
+
+            if games.is_empty() {
+                if self.retry_failed {
+                    info!("Nothing to retry, skipping");
+                } else {
+                    info!("Already checked in today, skipping");
+                }
+                continue;
+            }
+
+            let games = self.played_games(index, games);
+
+            if games.is_empty() {
+                continue;
+            }
+
+            let mut results = self.process(index, &games, progress.as_ref(), &mut breaker);
+
+            if results
+                .iter()
+                .any(|result| result.status == Status::CookieExpired)
+                && self.refresh_cookies(index)
+            {
+                info!("Retrying with refreshed cookies");
+                results = self.process(index, &games, progress.as_ref(), &mut breaker);
+            }
+
+            while results
+                .iter()
+                .any(|result| result.status == Status::CookieExpired)
+                && self.fall_back_cookies(index)
+            {
+                results = self.process(index, &games, progress.as_ref(), &mut breaker);
+            }
+
+            let missed = self.track_streaks(&mut results, started_at);
+
+            if let Some(progress) = &mut progress {
+                progress.finished(&results);
+            }
+
+            let redeemed = if results
+                .iter()
+                .any(|result| result.status == Status::CookieExpired)
+            {
+                Vec::new()
+            } else {
+                self.redeem_feed(index, &mut feed)
+            };
+
+            let account = &self.config.accounts[index];
+            let checkin = self.checkin(index);
+
+            let api_changed = results.iter().any(|result| {
+                result
+                    .error
+                    .as_ref()
+                    .is_some_and(CheckinError::is_api_change)
+            });
+
+            let mut notification = Notification::for_account(&account.name, &results);
+
+            for line in missed.iter().chain(&redeemed) {
+                notification.append(line);
+            }
+
+            let cookies_expired = results
+                .iter()
+                .any(|result| result.status == Status::CookieExpired);
+
+            let mut alert = account
+                .contact
+                .as_ref()
+                .and(Notification::alert_for_account(&account.name, &results));
+
+            if let (true, Some(refresh)) = (cookies_expired, &self.refresh) {
+                match refresh.link(&account.name) {
+                    Ok(link) => {
+                        let line = format!(
+                            "Cookies expired, paste fresh ones at {} (single use, valid for 24 hours)",
+                            link
+                        );
+
+                        notification.append(&line);
+
+                        if let Some(alert) = &mut alert {
+                            alert.append(&line);
+                        }
+                    }
+                    Err(e) => warn!("Failed to create cookie refresh link: {}", e),
+                }
+            }
+
+            alerts.extend(alert.map(|alert| (index, alert)));
+
+            if account.reminders {
+                match client::block_on(checkin.birthdays()) {
+                    Ok(names) => {
+                        if let Some(message) = reminders::birthday_message(&names) {
+                            info!("{}", message);
+                            notification.append(&message);
+                        }
+                    }
+                    Err(e) => warn!("Failed to fetch birthday reminders: {}", e),
+                }
+            }
+
+            if account.makeup_sign && !self.dry_run && !cookies_expired {
+                for &game in &games {
+                    let signed = results.iter().any(|result| {
+                        result.kind == RewardKind::Daily
+                            && result.game == game.name()
+                            && result.is_success()
+                    });
+
+                    if !signed {
+                        continue;
+                    }
+
+                    match client::block_on(checkin.makeup_sign(game)) {
+                        Ok(0) => {}
+                        Ok(days) => notification.append(&format!(
+                            "📅 {}: made up {} missed days",
+                            game.name(),
+                            days
+                        )),
+                        Err(e) => warn!("Failed to make up missed days for {}: {}", game.name(), e),
+                    }
+                }
+            }
+
+            if account.community && !self.dry_run && !cookies_expired {
+                match client::block_on(checkin.community_tasks()) {
+                    Ok(summary) => notification.append(&summary.to_string()),
+                    Err(e) => warn!("Failed to do the community missions: {}", e),
+                }
+            }
+
+            notifications.push((
+                index,
+                notification,
+                results.iter().map(Notified::from).collect::<Vec<_>>(),
+            ));
+            results.iter().for_each(events::result);
+            results.iter().for_each(error_reports::result);
+            report.extend(results);
+
+            if account.canary && api_changed {
+                error!(
+                "Canary account received an unexpected API response, skipping remaining accounts"
+            );
+                break;
+            }
+        }
+
+        drop(progress);
+
+        match self.output {
+            OutputFormat::Json => {
+                for result in &report {
+                    println!(
+                        "{}",
+                        serde_json::to_string(result).expect("Failed to serialize result")
+                    );
+                }
+            }
+            OutputFormat::Text if self.summary && !report.is_empty() => {
+                println!("{}", report::summary_table(&report, self.color));
+            }
+            OutputFormat::Text => {}
+        }
+
+        if self.gha {
+            gha::annotate(&report);
+            gha::write_summary(&report);
+        }
+
+        events::emit(
+            "run_finished",
+            json!({
+                "success": report.iter().all(GameResult::is_success),
+                "results": report.len(),
+                "seconds": clock::unix_now().saturating_sub(started_at),
+            }),
+        );
+
+        if let Some(path) = &self.output_file {
+            if let Err(e) = report::write(path, started_at, self.dry_run, &report) {
+                warn!("Failed to write results file {}: {}", path.display(), e);
+            }
+        }
+
+        if self.dry_run {
+            info!("Dry run: skipping notifications, healthchecks and state updates");
+            return report;
+        }
+
+        if let Some(moved) = self
+            .state
+            .as_mut()
+            .and_then(|store| store.quarantined.take())
+        {
+            notify::deliver(
+                self.client,
+                &self.config.notifiers,
+                &[Notification::state_quarantined(&moved)],
+            );
+        }
+
+        self.metrics.record_run(&report);
+
+        if let Some(path) = &self.config.metrics_file {
+            if let Err(e) = self.metrics.write(path) {
+                warn!("Failed to write metrics file {}: {}", path.display(), e);
+            }
+        }
+
+        if let Some(mqtt) = &self.config.mqtt {
+            mqtt.publish(&report);
+        }
+
+        let success = report.iter().all(GameResult::is_success);
+        let until_reset = clock::until_after_reset(clock::unix_now(), Duration::ZERO);
+
+        // No retry follows a shutdown, so it alerts right away.
+        if !success
+            && self.retry_follows
+            && self.grace_window.is_none_or(|grace| until_reset > grace)
+            && !self.shutdown.as_ref().is_some_and(Shutdown::requested)
+        {
+            warn!(
+                "Check-in failed, retrying before alerting ({} seconds until reset)",
+                until_reset.as_secs()
+            );
+        } else {
+            // Only now that they are sent do they count as notified, so a
+            // failure held back for a retry is still alerted on later.
+            for (index, mut notification, outcomes) in notifications {
+                if let (Some(store), true) = (&mut self.state, self.config.notify_on_change) {
+                    let (changed, recovered) = store.record_notified(outcomes);
+
+                    if !changed {
+                        debug!(
+                            "Not notifying about {}, nothing changed since the last notification",
+                            self.config.accounts[index].name
+                        );
+                        continue;
+                    }
+
+                    for line in &recovered {
+                        notification.append(line);
+                    }
+                }
+
+                queue.push(index, notification);
+            }
+
+            let notifications = if self.config.notify_per_account {
+                queue.drain()
+            } else {
+                Notification::combine(queue.drain()).into_iter().collect()
+            };
+            notify::deliver(self.client, &self.config.notifiers, &notifications);
+
+            for (index, alert) in alerts {
+                if let Some(contact) = &self.config.accounts[index].contact {
+                    notify::deliver(self.client, slice::from_ref(contact), &[alert]);
+                }
+            }
+
+            if let Some(healthcheck) = &self.config.healthcheck {
+                healthcheck.ping(self.client, &report);
+            }
+        }
+
+        self.record_run(started_at, &report, success);
+
+        report
+    }
+
+    /// Runs again what failed, as far as the state file tells.
+    fn retry(&mut self) -> Vec<GameResult> {
+        let retry_failed = mem::replace(&mut self.retry_failed, self.state.is_some());
+        let report = self.run();
+        self.retry_failed = retry_failed;
+        report
+    }
+
+    /// Runs only the given accounts instead of those selected with `--account`
+    /// and `--tag`.
+    fn run_accounts(&mut self, accounts: Vec<String>) -> Vec<GameResult> {
+        let filter = mem::replace(&mut self.account_filter, accounts);
+        let tags = mem::take(&mut self.tag_filter);
+        let report = self.run();
+        self.account_filter = filter;
+        self.tag_filter = tags;
+        report
+    }
+
+    /// The selected games the state file has no claim for today, and with
+    /// `--retry-failed` only those whose latest attempt failed. Without a
+    /// state file that is all of them.
+    fn pending_games(&self, index: usize, now: u64) -> Vec<&'a dyn Game> {
+        let account = &self.config.accounts[index].name;
+        let today = clock::server_date(now);
+
+        self.games
+            .iter()
+            .copied()
+            .filter(|game| {
+                self.force
+                    || !self
+                        .state
+                        .as_ref()
+                        .is_some_and(|store| store.claimed_on(account, game.name(), &today))
+            })
+            .filter(|game| {
+                !self.retry_failed
+                    || self
+                        .state
+                        .as_ref()
+                        .is_some_and(|store| store.failed_last(account, game.name()))
+            })
+            .collect()
+    }
+
+    /// The games the account has characters in, out of `games`: those in its
+    /// `games` if set, else those `detect_games` finds. All of them if the
+    /// lookup fails.
+    fn played_games(&self, index: usize, games: Vec<&'a dyn Game>) -> Vec<&'a dyn Game> {
+        let account = &self.config.accounts[index];
+
+        let played: Vec<&dyn Game> = match &account.games {
+            Some(names) => games
+                .into_iter()
+                .filter(|game| {
+                    cli::matches_filter(names, game.id()) || cli::matches_filter(names, game.name())
+                })
+                .collect(),
+            None if self.config.detect_games => {
+                match client::block_on(self.checkin(index).played_games()) {
+                    Ok(Some(ids)) => games
+                        .into_iter()
+                        .filter(|game| ids.contains(&game.hoyolab_id()))
+                        .collect(),
+                    Ok(None) => {
+                        warn!("No ltuid cookie to detect the account's games with, trying all");
+                        games
+                    }
+                    Err(e) => {
+                        warn!("Failed to detect the account's games, trying all: {}", e);
+                        games
+                    }
+                }
+            }
+            None => games,
+        };
+
+        if played.is_empty() {
+            info!("No characters in the selected games, skipping");
+        }
+
+        played
+    }
+
+    fn process(
+        &self,
+        index: usize,
+        games: &[&dyn Game],
+        progress: Option<&Progress>,
+        breaker: &mut CircuitBreaker,
+    ) -> Vec<GameResult> {
+        let account = &self.config.accounts[index];
+        let mut results = Vec::new();
+
+        // One game at a time, so the progress line can tell which is running
+        // and the breaker can stop a game between accounts.
+        for game in games {
+            if breaker.is_open(*game) {
+                results.push(GameResult::new(
+                    &account.name,
+                    *game,
+                    clock::unix_now(),
+                    Err(CheckinError::EndpointDown),
+                ));
+                continue;
+            }
+
+            if let Some(progress) = progress {
+                progress.game(game.name());
+            }
+
+            let game_results = client::block_on(
+                HoyolabCheckin::new(
+                    &self.config,
+                    account,
+                    &self.api,
+                    slice::from_ref(game),
+                    self.dry_run,
+                    self.interactive,
+                )
+                .process(),
+            );
+
+            breaker.record(*game, &game_results);
+            results.extend(game_results);
+        }
+
+        results
+    }
+
+    fn checkin(&self, index: usize) -> HoyolabCheckin<'_, TimedClient<'a>> {
+        HoyolabCheckin::new(
+            &self.config,
+            &self.config.accounts[index],
+            &self.api,
+            &self.games,
+            self.dry_run,
+            self.interactive,
+        )
+    }
+
+    /// Redeems the feed's codes the state file has no record of for the
+    /// account. `feed` caches the codes by game id for the rest of the run.
+    /// Returns a notification line per code redeemed.
+    fn redeem_feed(
+        &mut self,
+        index: usize,
+        feed: &mut HashMap<String, Vec<String>>,
+    ) -> Vec<String> {
+        let (Some(source), Some(store)) = (&self.config.gift_code_feed, &self.state) else {
+            return Vec::new();
+        };
+
+        let account = &self.config.accounts[index].name;
+        let checkin = self.checkin(index);
+        let mut redemptions = Vec::new();
+
+        for &game in self.games.iter().filter(|game| game.url_redeem().is_some()) {
+            let codes = match feed.get(game.id()) {
+                Some(codes) => codes,
+                None => {
+                    let codes =
+                        client::block_on(source.fetch(self.client, game)).unwrap_or_else(|e| {
+                            warn!("{}", e);
+                            Vec::new()
+                        });
+                    feed.entry(game.id().to_string()).or_insert(codes)
+                }
+            };
+
+            let new: Vec<String> = codes
+                .iter()
+                .filter(|code| !store.redeemed(account, game.name(), code))
+                .cloned()
+                .collect();
+
+            if new.is_empty() {
+                continue;
+            }
+
+            let _span = info_span!("game", game = %game.name()).entered();
+
+            if self.dry_run {
+                info!("Dry run: would redeem {}", new.join(", "));
+                continue;
+            }
+
+            match client::block_on(checkin.redeem(game, &new)) {
+                Ok(redeemed) => redemptions.extend(redeemed),
+                Err(e) => warn!("Failed to look up the character to redeem on: {}", e),
+            }
+        }
+
+        let lines = redemptions
+            .iter()
+            .filter(|redemption| redemption.status == RedeemStatus::Redeemed)
+            .map(|redemption| format!("🎁 {}: redeemed {}", redemption.game, redemption.code))
+            .collect();
+
+        if let Some(store) = &mut self.state {
+            store.record_redemptions(&redemptions);
+        }
+
+        lines
+    }
+
+    /// Renews an account's expired cookies from its `stoken` and saves them
+    /// to the config file. Returns whether there are new cookies to retry with.
+    fn refresh_cookies(&mut self, index: usize) -> bool {
+        if self.dry_run {
+            return false;
+        }
+
+        let cookies = match client::block_on(self.checkin(index).refresh_cookies()) {
+            None => return false,
+            Some(Ok(cookies)) => cookies,
+            Some(Err(e)) => {
+                warn!("Failed to refresh cookies from stoken: {}", e);
+                return false;
+            }
+        };
+
+        let account = &mut self.config.accounts[index];
+        account.cookies = cookies;
+
+        if let Err(e) = config_file::save_cookies(
+            &self.config_path,
+            self.profile.as_deref(),
+            &account.name,
+            &account.cookies.clone().into_iter().collect(),
+        ) {
+            warn!("{}, using the refreshed cookies for this run only", e);
+        }
+
+        true
+    }
+
+    /// Replaces the account's expired cookies with its next fallback set.
+    /// Returns whether there was one.
+    fn fall_back_cookies(&mut self, index: usize) -> bool {
+        let account = &mut self.config.accounts[index];
+
+        if account.fallback_cookies.is_empty() {
+            return false;
+        }
+
+        account.cookies = account.fallback_cookies.remove(0);
+        info!(
+            "Retrying with fallback cookies, {} more left",
+            account.fallback_cookies.len()
+        );
+        true
+    }
+
+    /// Fills in the streaks of the daily rewards claimed, from the state
+    /// file's history. Returns a warning line per game whose streak broke
+    /// since its last check-in.
+    fn track_streaks(&self, results: &mut [GameResult], now: u64) -> Vec<String> {
+        let Some(store) = &self.state else {
+            return Vec::new();
+        };
+        let mut missed = Vec::new();
+
+        for result in results.iter_mut().filter(|result| {
+            result.kind == RewardKind::Daily
+                && matches!(result.status, Status::Claimed | Status::AlreadyClaimed)
+        }) {
+            let (streak, last) = store.streak(&result.account, &result.game, now);
+            result.streak = Some(streak + 1);
+
+            if let (0, Some(last)) = (streak, last) {
+                let line = format!(
+                    "⚠ {}: missed a day, last checked in on {}",
+                    result.game, last
+                );
+                warn!("{}", line);
+                missed.push(line);
+            }
+        }
+
+        missed
+    }
+
+    fn record_run(&mut self, started_at: u64, report: &[GameResult], success: bool) {
+        if let Some(store) = &mut self.state {
+            store.record(report);
+            store.state.last_run = Some(LastRun {
+                started_at,
+                finished_at: clock::unix_now(),
+                success,
+            });
+
+            if let Err(e) = store.save() {
+                warn!("Failed to save state file: {}", e);
+            }
+        }
+    }
+}
+
+fn import_har(path: &Path) -> ExitCode {
+    match cookies::from_har(path) {
+        Ok(cookies) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&cookies).expect("Failed to serialize cookies")
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn validate_accounts(config: &Config, api: &TimedClient, args: &Args) -> ExitCode {
+    let mut code = ExitCode::SUCCESS;
+
+    for account in config
+        .accounts
+        .iter()
+        .filter(|account| account.selected(&args.accounts, &args.tags))
+    {
+        let _span = info_span!("account", account = %account.name).entered();
+        let checkin = HoyolabCheckin::new(config, account, api, &[], true, false);
+        let report = client::block_on(checkin.validate_cookies());
+
+        match report.status {
+            CookieStatus::Valid { .. } => {}
+            CookieStatus::Expired | CookieStatus::Malformed { .. } => {
+                code = ExitCode::from(EXIT_CONFIG)
+            }
+            CookieStatus::Unknown { .. } if code == ExitCode::SUCCESS => {
+                code = ExitCode::from(EXIT_NETWORK)
+            }
+            CookieStatus::Unknown { .. } => {}
+        }
+
+        if args.output == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::to_string(&report).expect("Failed to serialize cookie report")
+            );
+            continue;
+        }
+
+        match &report.status {
+            CookieStatus::Valid { nickname } => info!(
+                "Cookies are valid{}",
+                nickname
+                    .as_ref()
+                    .map(|nickname| format!(" (logged in as {})", nickname))
+                    .unwrap_or_default()
+            ),
+            CookieStatus::Expired => {
+                error!("Cookies have expired, log in to HoYoLAB again and update them")
+            }
+            CookieStatus::Malformed { reason } => error!("Cookies are malformed: {}", reason),
+            CookieStatus::Unknown { reason } => warn!("Could not check cookies: {}", reason),
+        }
+    }
+
+    code
+}
+
+fn show_rewards(config: &Config, api: &TimedClient, games: &[&dyn Game], args: &Args) -> ExitCode {
+    let mut code = ExitCode::SUCCESS;
+
+    for account in config
+        .accounts
+        .iter()
+        .filter(|account| account.selected(&args.accounts, &args.tags))
+    {
+        let _span = info_span!("account", account = %account.name).entered();
+        let checkin = HoyolabCheckin::new(config, account, api, &[], true, false);
+
+        for &game in games {
+            let calendar = match client::block_on(checkin.calendar(game)) {
+                Ok(calendar) => calendar,
+                Err(e) => {
+                    error!("Failed to get {} rewards: {}", game.name(), e);
+                    code = ExitCode::from(e.exit_code());
+                    continue;
+                }
+            };
+
+            match args.output {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string(&calendar).expect("Failed to serialize rewards")
+                ),
+                OutputFormat::Text => println!("{}\n", calendar),
+            }
+        }
+    }
+
+    code
+}
+
+/// Prints the Real-Time Notes of the selected games and sends a notification
+/// per account with the meters close to their cap.
+fn show_notes(
+    config: &Config,
+    client: &Client,
+    api: &TimedClient,
+    games: &[&dyn Game],
+    args: &Args,
+) -> ExitCode {
+    let threshold = config.notes_threshold.unwrap_or(0.9);
+    let mut notifications = Vec::new();
+    let mut code = ExitCode::SUCCESS;
+
+    for account in config
+        .accounts
+        .iter()
+        .filter(|account| account.selected(&args.accounts, &args.tags))
+    {
+        let _span = info_span!("account", account = %account.name).entered();
+        let checkin = HoyolabCheckin::new(config, account, api, &[], true, false);
+        let mut lines = Vec::new();
+
+        for &game in games {
+            let notes = match client::block_on(checkin.notes(game)) {
+                Ok(Some(notes)) => notes,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to get {} notes: {}", game.name(), e);
+                    code = ExitCode::from(e.exit_code());
+                    continue;
+                }
+            };
+
+            lines.extend(
+                notes
+                    .meters
+                    .iter()
+                    .filter(|meter| meter.near_cap(threshold))
+                    .map(|meter| format!("⚠ {}: {}", game.name(), meter)),
+            );
+
+            match args.output {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string(&notes).expect("Failed to serialize notes")
+                ),
+                OutputFormat::Text => println!("{}\n", notes),
+            }
+        }
+
+        if !lines.is_empty() {
+            notifications.push(Notification {
+                account: account.name.clone(),
+                title: format!("Almost capped for {}", account.name),
+                message: lines.join("\n"),
+                success: true,
+            });
+        }
+    }
+
+    if args.dry_run {
+        info!("Dry run: skipping notifications");
+    } else {
+        notify::deliver(client, &config.notifiers, &notifications);
+    }
+
+    code
+}
+
+/// Redeems the codes given on the command line on the selected games, or
+/// else the configured `gift_codes`.
+fn redeem_codes(
+    config: &Config,
+    api: &TimedClient,
+    games: &[&dyn Game],
+    codes: &[String],
+    args: &Args,
+) -> ExitCode {
+    if !codes.is_empty() && games.len() != 1 {
+        error!("Codes are game-specific, pick the game to redeem them on with --game");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if let Some(unknown) = config
+        .gift_codes
+        .keys()
+        .find(|id| games::find(id).is_none())
+    {
+        warn!("Ignoring gift codes for unknown game \"{}\"", unknown);
+    }
+
+    let mut code = ExitCode::SUCCESS;
+
+    for account in config
+        .accounts
+        .iter()
+        .filter(|account| account.selected(&args.accounts, &args.tags))
+    {
+        let _span = info_span!("account", account = %account.name).entered();
+        let checkin = HoyolabCheckin::new(config, account, api, &[], args.dry_run, false);
+
+        for &game in games {
+            let codes = match codes {
+                [] => config
+                    .gift_codes
+                    .iter()
+                    .find(|(id, _)| games::find(id).is_some_and(|found| found.id() == game.id()))
+                    .map_or(&[][..], |(_, codes)| codes),
+                codes => codes,
+            };
+
+            if codes.is_empty() {
+                continue;
+            }
+
+            let _span = info_span!("game", game = %game.name()).entered();
+
+            if args.dry_run {
+                info!("Dry run: would redeem {}", codes.join(", "));
+                continue;
+            }
+
+            let redemptions = match client::block_on(checkin.redeem(game, codes)) {
+                Ok(redemptions) => redemptions,
+                Err(e) => {
+                    error!("Failed to look up the character to redeem on: {}", e);
+                    code = ExitCode::from(e.exit_code());
+                    continue;
+                }
+            };
+
+            for redemption in redemptions {
+                if let Some(e) = &redemption.error {
+                    code = ExitCode::from(e.exit_code());
+                }
+
+                if args.output == OutputFormat::Json {
+                    println!(
+                        "{}",
+                        serde_json::to_string(&redemption).expect("Failed to serialize redemption")
+                    );
+                }
+            }
+        }
+    }
+
+    code
+}
+
+/// The lock file next to what instances would clash over: the state file,
+/// or else the config. `None` for a config from stdin without state.
+fn lock_path(config: &Config, config_path: &Path) -> Option<PathBuf> {
+    let path = match &config.state_file {
+        Some(path) => path,
+        None if config_file::is_stdin(config_path) => return None,
+        None => config_path,
+    };
+
+    let mut lock = path.to_path_buf().into_os_string();
+    lock.push(".lock");
+    Some(lock.into())
+}
+
+/// Gives the selected accounts their device identity from the state file,
+/// registering a new device with HoYoverse for accounts without one. Without
+/// a state file devices only last for the run. Dry runs register nothing.
+/// Returns the state file for the run to go on with.
+fn attach_devices(config: &mut Config, api: &TimedClient, args: &Args) -> Option<StateStore> {
+    let mut store = config.state_file.as_deref().map(StateStore::open);
+    let mut registered = false;
+
+    for index in 0..config.accounts.len() {
+        let name = config.accounts[index].name.clone();
+
+        if !config.accounts[index].selected(&args.accounts, &args.tags) {
+            continue;
+        }
+
+        let _span = info_span!("account", account = %name).entered();
+
+        if let Some(device) = store
+            .as_ref()
+            .and_then(|store| store.state.devices.get(&name))
+            .filter(|device| device.is_valid())
+        {
+            config.accounts[index].device = Some(device.clone());
+            continue;
+        }
+
+        if args.dry_run {
+            continue;
+        }
+
+        let mut device = match Device::generate() {
+            Ok(device) => device,
+            Err(e) => {
+                warn!("Failed to generate a device: {}", e);
+                continue;
+            }
+        };
+
+        let checkin = HoyolabCheckin::new(config, &config.accounts[index], api, &[], false, false);
+
+        match client::block_on(checkin.device_fp(&device)) {
+            Ok(fp) => {
+                debug!("Registered device {}", device.id);
+                device.fp = fp;
+            }
+            Err(e) => {
+                warn!("Failed to register a device: {}", e);
+                continue;
+            }
+        }
+
+        if let Some(store) = &mut store {
+            store.state.devices.insert(name, device.clone());
+            registered = true;
+        }
+
+        config.accounts[index].device = Some(device);
+    }
+
+    if let (Some(store), true) = (&store, registered) {
+        if let Err(e) = store.save() {
+            warn!("Failed to save devices to the state file: {}", e);
+        }
+    }
+
+    store
+}
+
+/// Succeeds if the last run succeeded and finished within the schedule's
+/// period (a day outside daemon mode) plus an hour. Unhealthy is always exit
+/// code 1, as Docker reserves 2.
+fn check_health(config: &Config, args: &Args) -> ExitCode {
+    let Some(path) = &config.state_file else {
+        error!("Health is read from the state file, set state_file in the config");
+        return ExitCode::FAILURE;
+    };
+
+    let period = match &args.mode {
+        Mode::DockerLoop(schedule) => schedule.period(),
+        Mode::Once | Mode::DockerOneshot | Mode::Lambda => Duration::from_secs(clock::DAY),
+    };
+    let max_age = period + Duration::from_secs(60 * 60);
+
+    let Some(last_run) = StateStore::read(path).state.last_run else {
+        println!("Unhealthy: no run recorded yet");
+        return ExitCode::FAILURE;
+    };
+
+    let age = clock::unix_now().saturating_sub(last_run.finished_at);
+    let finished = clock::rfc3339(last_run.finished_at);
+
+    if age > max_age.as_secs() {
+        println!(
+            "Unhealthy: the last run finished at {}, over {} seconds ago",
+            finished,
+            max_age.as_secs()
+        );
+        ExitCode::FAILURE
+    } else if !last_run.success {
+        println!("Unhealthy: the last run, finished at {}, failed", finished);
+        ExitCode::FAILURE
+    } else {
+        println!("Healthy: the last run finished at {}", finished);
+        ExitCode::SUCCESS
+    }
+}
+
+fn show_history(config: &Config, games: &[&dyn Game], args: &Args) -> ExitCode {
+    let Some(path) = &config.state_file else {
+        error!("No history is kept without a state_file in the config");
+        return ExitCode::from(EXIT_CONFIG);
+    };
+
+    for attempt in StateStore::read(path)
+        .state
+        .history
+        .iter()
+        .filter(|attempt| {
+            cli::matches_filter(&args.accounts, &attempt.account)
+                && games.iter().any(|game| game.name() == attempt.game)
+        })
+    {
+        if args.output == OutputFormat::Json {
+            println!(
+                "{}",
+                serde_json::to_string(attempt).expect("Failed to serialize history")
+            );
+            continue;
+        }
+
+        println!(
+            "{}  {}  {}{}  {}{}",
+            attempt.started_at,
+            attempt.account,
+            attempt.game,
+            match attempt.kind {
+                RewardKind::Daily => "",
+                RewardKind::Extra => " (bonus)",
+            },
+            attempt.status.label(),
+            match (&attempt.reward, attempt.retcode) {
+                (Some(reward), _) => format!(", {}", reward),
+                (None, Some(code)) => match retcode::meaning(code) {
+                    Some(meaning) => format!(", {} ({})", meaning, code),
+                    None => format!(", retcode {}", code),
+                },
+                (None, None) => String::new(),
+            }
+        );
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn generate_systemd(dir: &Path, args: &Args) -> ExitCode {
+    let paths = env::current_exe().and_then(|exe| Ok((exe, fs::canonicalize(&args.config)?)));
+    let (exe, config) = match paths {
+        Ok(paths) => paths,
+        Err(e) => {
+            error!("Failed to resolve the binary and config paths: {}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let exe = exe.display().to_string();
+    let config_arg = config.display().to_string();
+    let mut exec = vec![exe.as_str(), "--config", config_arg.as_str()];
+
+    if let Some(profile) = &args.profile {
+        exec.extend(["--profile", profile.as_str()]);
+    }
+
+    // Run with sudo to write into /etc, the units are still for whoever ran it.
+    let user = env::var("SUDO_USER").or_else(|_| env::var("USER")).ok();
+    let schedule = match &args.mode {
+        Mode::DockerLoop(schedule) => Some(schedule),
+        Mode::Once | Mode::DockerOneshot | Mode::Lambda => None,
+    };
+    let (service, timer) = systemd::units(
+        &exec,
+        config.parent().unwrap_or(Path::new("/")),
+        user.as_deref(),
+        schedule,
+    );
+
+    for (name, contents) in [(systemd::SERVICE, service), (systemd::TIMER, timer)] {
+        let path = dir.join(name);
+
+        if let Err(e) = fs::write(&path, contents) {
+            error!("Failed to write {}: {}", path.display(), e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+
+        info!("Wrote {}", path.display());
+    }
+
+    info!(
+        "Install them with: sudo cp {} {} /etc/systemd/system/ && sudo systemctl enable --now {}",
+        dir.join(systemd::SERVICE).display(),
+        dir.join(systemd::TIMER).display(),
+        systemd::TIMER
+    );
+    ExitCode::SUCCESS
+}
+
+fn show_stats(
+    config: &Config,
+    games: &[&dyn Game],
+    windows: &[(String, Duration)],
+    args: &Args,
+) -> ExitCode {
+    let Some(path) = &config.state_file else {
+        error!("No history is kept without a state_file in the config");
+        return ExitCode::from(EXIT_CONFIG);
+    };
+
+    let store = StateStore::read(path);
+    let history = store
+        .state
+        .history
+        .iter()
+        .filter(|attempt| cli::matches_filter(&args.accounts, &attempt.account));
+    let now = clock::unix_now();
+
+    for (index, (window, duration)) in windows.iter().enumerate() {
+        let stats = stats::collect(history.clone(), games, (window, *duration), now);
+
+        if args.output == OutputFormat::Json {
+            for stats in &stats {
+                println!(
+                    "{}",
+                    serde_json::to_string(stats).expect("Failed to serialize stats")
+                );
+            }
+            continue;
+        }
+
+        if index > 0 {
+            println!();
+        }
+
+        println!("Last {}\n{}", window, stats::table(&stats));
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            logging::init(false, false, 0, None, None);
+            error!("{}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    // Machine-readable output owns stdout, so logs move to stderr.
+    let log_to_stderr =
+        args.output != OutputFormat::Text || args.events.as_deref() == Some(Path::new("-"));
+
+    if let Command::Features = args.command {
+        println!("{}", features::report());
+        return ExitCode::SUCCESS;
+    }
+
+    if let Command::ImportHar(path) = &args.command {
+        logging::init(
+            log_to_stderr,
+            args.color(log_to_stderr),
+            args.verbosity,
+            None,
+            None,
+        );
+        return import_har(path);
+    }
+
+    if let Command::GenerateSystemd(dir) = &args.command {
+        logging::init(
+            log_to_stderr,
+            args.color(log_to_stderr),
+            args.verbosity,
+            None,
+            None,
+        );
+        return generate_systemd(dir, &args);
+    }
+
+    if let Command::AddAccount = args.command {
+        logging::init(
+            log_to_stderr,
+            args.color(log_to_stderr),
+            args.verbosity,
+            None,
+            None,
+        );
+
+        return match setup::add_account(&args.config, args.profile.as_deref(), &Client::new()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                error!("{}", e);
+                ExitCode::from(EXIT_CONFIG)
+            }
+        };
+    }
+
+    if let Command::ImportCookies {
+        browser,
+        account,
+        database,
+    } = &args.command
+    {
+        logging::init(
+            log_to_stderr,
+            args.color(log_to_stderr),
+            args.verbosity,
+            None,
+            None,
+        );
+
+        return match setup::import_cookies(
+            &args.config,
+            args.profile.as_deref(),
+            &Client::new(),
+            *browser,
+            account,
+            database.as_deref(),
+        ) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                error!("{}", e);
+                ExitCode::from(EXIT_CONFIG)
+            }
+        };
+    }
+
+    let mut config = match load_config(&args.config, args.profile.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            logging::init(
+                log_to_stderr,
+                args.color(log_to_stderr),
+                args.verbosity,
+                None,
+                None,
+            );
+            return startup_failed(&args, EXIT_CONFIG, e);
+        }
+    };
+
+    logging::init(
+        log_to_stderr,
+        args.color(log_to_stderr),
+        args.verbosity,
+        config.log_file.as_ref(),
+        config.otlp.as_ref(),
+    );
+
+    let _sentry = match config.sentry.as_ref().map(error_reports::init).transpose() {
+        Ok(guard) => guard,
+        Err(e) => {
+            return startup_failed(
+                &args,
+                EXIT_CONFIG,
+                format!("Failed to set up Sentry: {}", e),
+            );
+        }
+    };
+
+    if let Some(profile) = &args.profile {
+        info!("Using profile \"{}\"", profile);
+    }
+
+    if let Some(unknown) = args
+        .accounts
+        .iter()
+        .find(|name| {
+            !config
+                .accounts
+                .iter()
+                .any(|a| a.name.eq_ignore_ascii_case(name))
+        })
+        .or_else(|| args.games.iter().find(|name| games::find(name).is_none()))
+    {
+        error!("No account or game named \"{}\"", unknown);
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if let Some(unknown) = args.tags.iter().find(|tag| {
+        !config
+            .accounts
+            .iter()
+            .any(|a| cli::matches_filter(&a.tags, tag))
+    }) {
+        error!("No account tagged \"{}\"", unknown);
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if let Some(path) = &args.events {
+        if let Err(e) = events::open(path) {
+            error!("Failed to open event stream {}: {}", path.display(), e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+
+    if let Some(dir) = &args.debug_dump {
+        if let Err(e) = dump::enable(dir) {
+            error!(
+                "Failed to create debug dump directory {}: {}",
+                dir.display(),
+                e
+            );
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+
+    if let Some(path) = &config.audit_log {
+        if let Err(e) = audit::open(path) {
+            return startup_failed(
+                &args,
+                EXIT_CONFIG,
+                format!("Failed to open audit log {}: {}", path.display(), e),
+            );
+        }
+    }
+
+    if args.retry_failed && config.state_file.is_none() {
+        error!("--retry-failed needs a state_file to know which check-ins failed");
+        return ExitCode::from(EXIT_CONFIG);
+    }
+
+    if let Some(unknown) = config
+        .games
+        .keys()
+        .find(|id| !GAMES.iter().any(|game| game.id() == id.as_str()))
+    {
+        return startup_failed(
+            &args,
+            EXIT_CONFIG,
+            format!("No game with the id \"{}\" to override", unknown),
+        );
+    }
+
+    if let Some((account, unknown)) = config.accounts.iter().find_map(|account| {
+        account
+            .games
+            .iter()
+            .flatten()
+            .find(|name| games::find(name).is_none())
+            .map(|name| (&account.name, name))
+    }) {
+        return startup_failed(
+            &args,
+            EXIT_CONFIG,
+            format!(
+                "No game \"{}\" in the games of account {}",
+                unknown, account
+            ),
+        );
+    }
+
+    if let Command::CheckConfig = args.command {
+        info!(
+            "Config is valid: {} accounts, {} notifiers",
+            config.accounts.len(),
+            config.notifiers.len()
+        );
+        return ExitCode::SUCCESS;
+    }
+
+    let client = Client::new();
+
+    if config.discover_act_ids {
+        let discovered = client::block_on(discovery::discover(&client, config.base_url.as_deref()));
+
+        for (id, discovered) in discovered {
+            config
+                .games
+                .entry(id.to_string())
+                .or_default()
+                .discovered(discovered);
+        }
+    }
+
+    let games: Vec<&dyn Game> = GAMES
+        .iter()
+        .copied()
+        .filter(|game| {
+            cli::matches_filter(&args.games, game.id())
+                || cli::matches_filter(&args.games, game.name())
+        })
+        .map(|game| games::configure(game, config.games.get(game.id())))
+        .collect();
+
+    if let Command::History = args.command {
+        return show_history(&config, &games, &args);
+    }
+
+    if let Command::Stats(windows) = &args.command {
+        return show_stats(&config, &games, windows, &args);
+    }
+
+    if let Command::Health = args.command {
+        return check_health(&config, &args);
+    }
+
+    let impersonate = match config.impersonate.then(Impersonate::new).transpose() {
+        Ok(impersonate) => impersonate,
+        Err(e) => {
+            error!("Failed to set up the impersonating client: {}", e);
+            return ExitCode::from(EXIT_CONFIG);
+        }
+    };
+    let backend = match &impersonate {
+        Some(impersonate) => Backend::Impersonate(impersonate),
+        None => Backend::Reqwest(&client),
+    };
+    // Every HoYoLAB request, subcommands and device registration included,
+    // goes through the configured client and is timed.
+    let metrics = Arc::new(Metrics::new());
+    let api = TimedClient::new(backend, Arc::clone(&metrics));
+
+    // Only the commands that check in or redeem; the others are safe to run
+    // alongside, e.g. `validate` while the daemon is up. Lambda keeps its own
+    // instances apart, and its config directory is read-only.
+    let _lock = match (&args.command, lock_path(&config, &args.config)) {
+        (Command::Run | Command::Redeem(_), Some(path)) if !matches!(args.mode, Mode::Lambda) => {
+            match lock::acquire(&path) {
+                Ok(lock) => Some(lock),
+                Err(e) => {
+                    error!("{}", e);
+                    return ExitCode::from(EXIT_LOCKED);
+                }
+            }
+        }
+        _ => None,
+    };
+
+    // Ahead of registering devices, which can take a while with many
+    // accounts, so a signal from here on already waits for the run.
+    let shutdown = (matches!(args.command, Command::Run)
+        && matches!(args.mode, Mode::DockerOneshot | Mode::DockerLoop(_)))
+    .then(|| Shutdown::install().expect("Failed to install signal handlers"));
+
+    let state = attach_devices(&mut config, &api, &args);
+
+    if let Command::Validate = args.command {
+        return validate_accounts(&config, &api, &args);
+    }
+
+    if let Command::Rewards = args.command {
+        return show_rewards(&config, &api, &games, &args);
+    }
+
+    if let Command::Notes = args.command {
+        return show_notes(&config, &client, &api, &games, &args);
+    }
+
+    if let Command::Redeem(codes) = &args.command {
+        return redeem_codes(&config, &api, &games, codes, &args);
+    }
+
+    let mut runner = Runner {
+        config_path: args.config.clone(),
+        profile: args.profile.clone(),
+        client: &client,
+        account_filter: args.accounts.clone(),
+        tag_filter: args.tags.clone(),
+        games,
+        output: args.output,
+        summary: args.verbosity >= 0 && !log_to_stderr,
+        color: args.color(false),
+        progress: args.output == OutputFormat::Text
+            && args.verbosity >= 0
+            && io::stderr().is_terminal(),
+        output_file: args.output_file.clone(),
+        gha: args.gha,
+        dry_run: args.dry_run,
+        interactive: matches!(args.mode, Mode::Once) && io::stdin().is_terminal(),
+        force: args.force,
+        retry_failed: args.retry_failed,
+        grace_window: match args.mode {
+            Mode::DockerLoop(_) => config.daemon.grace_window.map(|grace| grace.0),
+            _ => None,
+        },
+        retry_follows: false,
+        state,
+        metrics,
+        api,
+        refresh: None,
+        shutdown,
+        watchdog: None,
+        config,
+    };
+
+    match args.mode {
+        Mode::Once | Mode::DockerOneshot => exit_code(&runner.run()),
+        Mode::Lambda => {
+            error!("{}", lambda::serve(|| runner.run()));
+            ExitCode::from(EXIT_CONFIG)
+        }
+        Mode::DockerLoop(schedule) => {
+            let shutdown = runner.shutdown.clone().expect("Installed for docker modes");
+            let control = Arc::new(Control::default());
+            let systemd = Rc::new(systemd::Notifier::from_env());
+            runner.watchdog = Some(Rc::clone(&systemd));
+
+            if let Some(server) = &runner.config.server {
+                runner.refresh = server
+                    .public_url
+                    .as_deref()
+                    .filter(|_| cfg!(feature = "server") && !config_file::is_stdin(&args.config))
+                    .map(|url| {
+                        Arc::new(CookieRefresh::new(
+                            url,
+                            &args.config,
+                            args.profile.as_deref(),
+                        ))
+                    });
+
+                server::spawn(
+                    server,
+                    Arc::clone(&runner.metrics),
+                    runner.refresh.clone(),
+                    Arc::clone(&control),
+                );
+            }
+
+            let settings = &runner.config.daemon;
+            let retry = (settings.grace_window.is_some() || settings.retries.is_some()).then_some(
+                daemon::Retry {
+                    interval: settings.retry_interval.0,
+                    limit: settings.retries,
+                },
+            );
+
+            let watch = if config_file::is_stdin(&args.config) {
+                None
+            } else {
+                ConfigWatch::install(&args.config)
+                    .inspect_err(|e| warn!("Not watching the config for changes: {}", e))
+                    .ok()
+            };
+
+            daemon::run_loop(
+                &systemd,
+                &shutdown,
+                &schedule,
+                retry,
+                &control,
+                watch,
+                |wake| {
+                    runner.retry_follows = matches!(
+                        wake,
+                        Wake::Schedule { last: false } | Wake::Retry { last: false }
+                    );
+
+                    let report = match wake {
+                        Wake::Reload => {
+                            match runner.reload() {
+                                Ok(()) => info!(
+                                    "Reloaded {} accounts and {} notifiers",
+                                    runner.config.accounts.len(),
+                                    runner.config.notifiers.len()
+                                ),
+                                Err(e) => {
+                                    error!(
+                                        "Failed to reload the config, keeping the old one: {}",
+                                        e
+                                    )
+                                }
+                            }
+
+                            return true;
+                        }
+                        Wake::Schedule { .. } | Wake::Request(Trigger::All) => runner.run(),
+                        Wake::Retry { .. } => runner.retry(),
+                        Wake::Request(Trigger::Account(name)) => {
+                            if !runner
+                                .config
+                                .accounts
+                                .iter()
+                                .any(|account| account.name.eq_ignore_ascii_case(&name))
+                            {
+                                warn!("No account named \"{}\" to check in", name);
+                                return true;
+                            }
+
+                            runner.run_accounts(vec![name])
+                        }
+                    };
+
+                    control.publish(&report);
+                    report.iter().all(GameResult::is_success)
+                },
+            );
+
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,269 @@
+use crate::{
+    client::{self, Backend, HttpClient, Request, Response},
+    clock,
+    report::{GameResult, RewardKind, Status},
+};
+use std::{
+    fmt::Write,
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Upper bounds of the request latency histogram, in seconds.
+const LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Run statistics exposed in the Prometheus text format.
+pub struct Metrics {
+    started: Instant,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    runs: u64,
+    last_run: Option<u64>,
+    last_success: Option<u64>,
+    last_run_succeeded: bool,
+    /// Latest result per (account, game, kind).
+    results: Vec<(String, String, RewardKind, bool)>,
+    /// Results of every run so far, per status.
+    statuses: Vec<(Status, u64)>,
+    /// Requests per latency bucket, the last one being `+Inf`.
+    latency_buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    latency_sum: f64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn record_run(&self, report: &[GameResult]) {
+        let mut inner = self.inner.lock().expect("Metrics poisoned");
+        let now = clock::unix_now();
+        let success = report.iter().all(GameResult::is_success);
+
+        inner.runs += 1;
+        inner.last_run = Some(now);
+        inner.last_run_succeeded = success;
+
+        if success {
+            inner.last_success = Some(now);
+        }
+
+        for result in report {
+            match inner
+                .statuses
+                .iter_mut()
+                .find(|(status, _)| *status == result.status)
+            {
+                Some((_, count)) => *count += 1,
+                None => inner.statuses.push((result.status, 1)),
+            }
+
+            let value = (
+                result.account.clone(),
+                result.game.clone(),
+                result.kind,
+                result.is_success(),
+            );
+
+            match inner.results.iter_mut().find(|(account, game, kind, _)| {
+                *account == result.account && *game == result.game && *kind == result.kind
+            }) {
+                Some(existing) => *existing = value,
+                None => inner.results.push(value),
+            }
+        }
+    }
+
+    pub fn record_request(&self, duration: Duration) {
+        let mut inner = self.inner.lock().expect("Metrics poisoned");
+        let seconds = duration.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        inner.latency_buckets[bucket] += 1;
+        inner.latency_sum += seconds;
+    }
+
+    /// Writes the metrics for Prometheus' node exporter textfile collector,
+    /// replacing the file atomically so it never scrapes a partial write.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut temp = path.to_path_buf().into_os_string();
+        temp.push(".tmp");
+
+        fs::write(&temp, self.render())?;
+        fs::rename(&temp, path)
+    }
+
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().expect("Metrics poisoned");
+        let mut out = String::new();
+
+        gauge(
+            &mut out,
+            "hoyo_checkin_uptime_seconds",
+            "Seconds since the process started.",
+            self.started.elapsed().as_secs(),
+        );
+        counter(
+            &mut out,
+            "hoyo_checkin_runs_total",
+            "Check-in runs performed.",
+            inner.runs,
+        );
+
+        if let Some(last_run) = inner.last_run {
+            gauge(
+                &mut out,
+                "hoyo_checkin_last_run_timestamp_seconds",
+                "Unix time the last run finished.",
+                last_run,
+            );
+            gauge(
+                &mut out,
+                "hoyo_checkin_last_run_success",
+                "Whether every check-in of the last run succeeded.",
+                u64::from(inner.last_run_succeeded),
+            );
+        }
+
+        if let Some(last_success) = inner.last_success {
+            gauge(
+                &mut out,
+                "hoyo_checkin_last_success_timestamp_seconds",
+                "Unix time of the last fully successful run.",
+                last_success,
+            );
+        }
+
+        if !inner.statuses.is_empty() {
+            let _ = writeln!(
+                out,
+                "# HELP hoyo_checkin_results_total Check-in results by status.\n# TYPE hoyo_checkin_results_total counter"
+            );
+
+            for (status, count) in &inner.statuses {
+                let _ = writeln!(
+                    out,
+                    "hoyo_checkin_results_total{{status=\"{}\"}} {}",
+                    match status {
+                        Status::Claimed => "claimed",
+                        Status::AlreadyClaimed => "already_claimed",
+                        Status::WouldClaim => "would_claim",
+                        Status::CaptchaRequired => "captcha_required",
+                        Status::CookieExpired => "cookie_expired",
+                        Status::Failed => "failed",
+                    },
+                    count
+                );
+            }
+        }
+
+        let requests: u64 = inner.latency_buckets.iter().sum();
+
+        if requests > 0 {
+            let _ = writeln!(
+                out,
+                "# HELP hoyo_checkin_request_duration_seconds Latency of requests to HoYoLAB.\n# TYPE hoyo_checkin_request_duration_seconds histogram"
+            );
+
+            let mut cumulative = 0;
+
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&inner.latency_buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "hoyo_checkin_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+                    bound, cumulative
+                );
+            }
+
+            let _ = writeln!(
+                out,
+                "hoyo_checkin_request_duration_seconds_bucket{{le=\"+Inf\"}} {}\nhoyo_checkin_request_duration_seconds_sum {}\nhoyo_checkin_request_duration_seconds_count {}",
+                requests, inner.latency_sum, requests
+            );
+        }
+
+        if !inner.results.is_empty() {
+            let _ = writeln!(
+                out,
+                "# HELP hoyo_checkin_success Whether the latest check-in per account and game succeeded.\n# TYPE hoyo_checkin_success gauge"
+            );
+
+            for (account, game, kind, success) in &inner.results {
+                let _ = writeln!(
+                    out,
+                    "hoyo_checkin_success{{account=\"{}\",game=\"{}\",kind=\"{}\"}} {}",
+                    escape(account),
+                    escape(game),
+                    match kind {
+                        RewardKind::Daily => "daily",
+                        RewardKind::Extra => "extra",
+                    },
+                    u8::from(*success)
+                );
+            }
+        }
+
+        out
+    }
+}
+
+/// The HTTP client handed to the check-in logic, timing every request.
+pub struct TimedClient<'a> {
+    client: Backend<'a>,
+    metrics: Arc<Metrics>,
+}
+
+impl<'a> TimedClient<'a> {
+    pub fn new(client: Backend<'a>, metrics: Arc<Metrics>) -> Self {
+        Self { client, metrics }
+    }
+}
+
+impl HttpClient for TimedClient<'_> {
+    async fn execute(&self, request: Request) -> Result<Response, client::Error> {
+        let started = Instant::now();
+        let response = self.client.execute(request).await;
+
+        self.metrics.record_request(started.elapsed());
+        response
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.client.sleep(duration).await
+    }
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(
+        out,
+        "# HELP {} {}\n# TYPE {} gauge\n{} {}",
+        name, help, name, name, value
+    );
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(
+        out,
+        "# HELP {} {}\n# TYPE {} counter\n{} {}",
+        name, help, name, name, value
+    );
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
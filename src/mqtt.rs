@@ -0,0 +1,221 @@
+use crate::report::{GameResult, RewardKind};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+use tracing::{debug, warn};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An MQTT broker to publish every result to, retained, along with Home
+/// Assistant discovery messages that create a sensor per account and game.
+/// Spoken as plain MQTT 3.1.1 without TLS, so keep the broker local.
+#[derive(Deserialize)]
+pub struct Mqtt {
+    host: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    username: Option<String>,
+    password: Option<String>,
+    #[serde(default = "default_client_id")]
+    client_id: String,
+    /// Results are published to `<topic_prefix>/<account>/<game>/state`.
+    #[serde(default = "default_topic_prefix")]
+    topic_prefix: String,
+    /// Home Assistant's discovery prefix.
+    #[serde(default = "default_discovery_prefix")]
+    discovery_prefix: String,
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_client_id() -> String {
+    env!("CARGO_PKG_NAME").to_string()
+}
+
+fn default_topic_prefix() -> String {
+    "hoyo-checkin".to_string()
+}
+
+fn default_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
+
+impl Mqtt {
+    pub fn publish(&self, report: &[GameResult]) {
+        if report.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.try_publish(report) {
+            warn!(
+                "Failed to publish results to MQTT broker {}:{}: {}",
+                self.host, self.port, e
+            );
+        }
+    }
+
+    fn try_publish(&self, report: &[GameResult]) -> io::Result<()> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        self.connect(&mut stream)?;
+
+        for result in report {
+            let account = slug(&result.account);
+            let game = match result.kind {
+                RewardKind::Daily => slug(&result.game),
+                RewardKind::Extra => format!("{}_bonus", slug(&result.game)),
+            };
+            let id = format!("hoyo_checkin_{}_{}", account, game);
+            let state_topic = format!("{}/{}/{}/state", self.topic_prefix, account, game);
+
+            let name = match result.kind {
+                RewardKind::Daily => format!("{} check-in", result.game),
+                RewardKind::Extra => format!("{} event bonus", result.game),
+            };
+            let device = json!({
+                "identifiers": [format!("hoyo_checkin_{}", account)],
+                "name": format!("HoYoLAB {}", result.account),
+                "manufacturer": env!("CARGO_PKG_NAME"),
+            });
+
+            // The status with the full result as attributes, and when it was
+            // last checked as a timestamp sensor of its own.
+            let sensors = [
+                (
+                    id.clone(),
+                    json!({
+                        "name": name,
+                        "value_template": "{{ value_json.status }}",
+                        "json_attributes_topic": state_topic,
+                        "icon": "mdi:calendar-check",
+                    }),
+                ),
+                (
+                    format!("{}_time", id),
+                    json!({
+                        "name": format!("{} time", name),
+                        "value_template": "{{ value_json.finished_at }}",
+                        "device_class": "timestamp",
+                    }),
+                ),
+            ];
+
+            for (id, mut discovery) in sensors {
+                discovery["unique_id"] = json!(id);
+                discovery["object_id"] = json!(id);
+                discovery["state_topic"] = json!(state_topic);
+                discovery["device"] = device.clone();
+
+                publish(
+                    &mut stream,
+                    &format!("{}/sensor/{}/config", self.discovery_prefix, id),
+                    discovery.to_string().as_bytes(),
+                )?;
+            }
+
+            publish(
+                &mut stream,
+                &state_topic,
+                &serde_json::to_vec(result).expect("Failed to serialize result"),
+            )?;
+        }
+
+        debug!("Published {} results over MQTT", report.len());
+
+        // DISCONNECT
+        stream.write_all(&[0xe0, 0])
+    }
+
+    fn connect(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let mut flags = 0x02; // Clean session.
+        let mut payload = string(&self.client_id);
+
+        if let Some(username) = &self.username {
+            flags |= 0x80;
+            payload.extend(string(username));
+        }
+
+        if let Some(password) = &self.password {
+            flags |= 0x40;
+            payload.extend(string(password));
+        }
+
+        let mut body = string("MQTT");
+        body.extend([4, flags, 0, 60]); // Protocol level 4, 60s keepalive.
+        body.extend(payload);
+        stream.write_all(&packet(0x10, &body))?;
+
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+
+        match connack {
+            [0x20, 2, _, 0] => Ok(()),
+            [0x20, 2, _, 4 | 5] => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the broker rejected the username or password",
+            )),
+            [0x20, 2, _, code] => Err(io::Error::other(format!(
+                "the broker refused the connection with code {}",
+                code
+            ))),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an MQTT broker",
+            )),
+        }
+    }
+}
+
+/// A retained QoS 0 PUBLISH.
+fn publish(stream: &mut TcpStream, topic: &str, payload: &[u8]) -> io::Result<()> {
+    let mut body = string(topic);
+    body.extend_from_slice(payload);
+    stream.write_all(&packet(0x31, &body))
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = body.len();
+
+    loop {
+        let byte = (length % 128) as u8;
+        length /= 128;
+
+        if length == 0 {
+            packet.push(byte);
+            break;
+        }
+
+        packet.push(byte | 0x80);
+    }
+
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn string(value: &str) -> Vec<u8> {
+    let mut bytes = (value.len() as u16).to_be_bytes().to_vec();
+    bytes.extend_from_slice(value.as_bytes());
+    bytes
+}
+
+/// Lowercase letters, digits and underscores, as Home Assistant ids allow.
+fn slug(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
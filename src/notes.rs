@@ -0,0 +1,112 @@
+use crate::{client::HttpClient, ds, games::Game, CheckinError, HoyolabCheckin, SignResponse};
+use http::Method;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::fmt;
+use tracing::warn;
+
+/// A resource that refills over time, such as resin.
+#[derive(Serialize)]
+pub struct Meter {
+    pub name: &'static str,
+    pub current: u32,
+    pub max: u32,
+    /// Seconds until `current` reaches `max`.
+    pub full_in: u64,
+}
+
+impl Meter {
+    /// Whether the meter is at or above `threshold` (a fraction) of its cap.
+    pub fn near_cap(&self, threshold: f64) -> bool {
+        self.max > 0 && f64::from(self.current) >= f64::from(self.max) * threshold
+    }
+}
+
+impl fmt::Display for Meter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}/{}", self.name, self.current, self.max)?;
+
+        match (self.full_in / 3600, self.full_in % 3600 / 60) {
+            _ if self.current >= self.max => write!(f, ", full"),
+            (0, minutes) => write!(f, ", full in {}m", minutes.max(1)),
+            (hours, minutes) => write!(f, ", full in {}h {}m", hours, minutes),
+        }
+    }
+}
+
+/// One account's Real-Time Notes for one game.
+#[derive(Serialize)]
+pub struct Notes {
+    pub account: String,
+    pub game: String,
+    pub meters: Vec<Meter>,
+}
+
+impl fmt::Display for Notes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} — {}", self.account, self.game)?;
+
+        for meter in &self.meters {
+            write!(f, "\n  {}", meter)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Recovery times come as numbers from some games and strings from others.
+pub fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Number(u64),
+        Text(String),
+    }
+
+    match Seconds::deserialize(deserializer)? {
+        Seconds::Number(seconds) => Ok(seconds),
+        Seconds::Text(text) => text.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// The game's Real-Time Notes for the character codes would be redeemed
+    /// on. `None` if the game has none or the account has no character.
+    pub async fn notes(&self, game: &dyn Game) -> Result<Option<Notes>, CheckinError> {
+        let Some(url) = game.url_notes() else {
+            return Ok(None);
+        };
+
+        let Some(role) = self.role(game).await? else {
+            warn!("No {} character found to read notes of", game.name());
+            return Ok(None);
+        };
+
+        let mut request = self.request(
+            game,
+            Method::GET,
+            url,
+            &[("server", &role.region), ("role_id", &role.game_uid)],
+            Vec::new(),
+        )?;
+
+        ds::sign(request.headers_mut(), &role.game_uid, self.lang());
+
+        let response: SignResponse<Value> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        let meters = game
+            .notes(response.data.unwrap_or_default())
+            .map_err(|e| CheckinError::UnexpectedResponse(e.to_string()))?;
+
+        Ok(Some(Notes {
+            account: self.account.name.clone(),
+            game: game.name().to_string(),
+            meters,
+        }))
+    }
+}
@@ -0,0 +1,69 @@
+//! Native desktop notifications, shown through the tool each platform ships
+//! with rather than linking against its notification APIs. The title and
+//! message are passed in the environment so they never need quoting.
+
+use super::Notification;
+use std::{error::Error, process::Command};
+
+#[cfg(target_os = "macos")]
+fn command(_notification: &Notification) -> Command {
+    let mut command = Command::new("osascript");
+    command.args([
+        "-e",
+        r#"display notification (system attribute "HOYO_MESSAGE") with title (system attribute "HOYO_TITLE")"#,
+    ]);
+    command
+}
+
+#[cfg(windows)]
+fn command(_notification: &Notification) -> Command {
+    // Toasts need a registered app id, so they are shown as PowerShell's.
+    const SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $template.GetElementsByTagName('text')
+$text.Item(0).AppendChild($template.CreateTextNode($env:HOYO_TITLE)) | Out-Null
+$text.Item(1).AppendChild($template.CreateTextNode($env:HOYO_MESSAGE)) | Out-Null
+$toast = [Windows.UI.Notifications.ToastNotification]::new($template)
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe').Show($toast)
+"#;
+
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT]);
+    command
+}
+
+/// libnotify's `notify-send`, on Linux and the BSDs.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn command(notification: &Notification) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args([
+        "--app-name",
+        env!("CARGO_PKG_NAME"),
+        "--urgency",
+        if notification.success {
+            "normal"
+        } else {
+            "critical"
+        },
+        &notification.title,
+        &notification.message,
+    ]);
+    command
+}
+
+pub fn send(notification: &Notification) -> Result<(), Box<dyn Error>> {
+    let mut command = command(notification);
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .env("HOYO_TITLE", &notification.title)
+        .env("HOYO_MESSAGE", &notification.message)
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status).into());
+    }
+
+    Ok(())
+}
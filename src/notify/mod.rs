@@ -0,0 +1,382 @@
+mod desktop;
+mod queue;
+
+pub use queue::NotificationQueue;
+
+use crate::report::{GameResult, RewardKind, Status};
+use hmac::{Hmac, KeyInit, Mac};
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use std::{
+    error::Error,
+    io::Write,
+    mem,
+    path::Path,
+    process::{Command, Stdio},
+};
+use tracing::{debug, warn};
+
+/// Longest message Discord accepts in a webhook's `content`.
+const DISCORD_LIMIT: usize = 2000;
+/// Longest message Telegram's `sendMessage` accepts.
+const TELEGRAM_LIMIT: usize = 4096;
+
+/// A channel run results are delivered to.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notifier {
+    Discord {
+        webhook_url: String,
+    },
+    Telegram {
+        bot_token: String,
+        chat_id: String,
+    },
+    /// POSTs the notification as JSON to an arbitrary URL.
+    Webhook {
+        url: String,
+        /// Signs the body like GitHub webhooks do, with its HMAC-SHA256
+        /// under this secret in `X-Hub-Signature-256`.
+        secret: Option<String>,
+    },
+    /// Mails the notification through the local `sendmail` (or a compatible
+    /// command such as msmtp).
+    Email {
+        to: String,
+        sendmail: Option<String>,
+    },
+    /// Shows a native notification on the machine the tool runs on, for
+    /// running at login rather than on a server.
+    Desktop,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Notification {
+    pub account: String,
+    pub title: String,
+    pub message: String,
+    pub success: bool,
+}
+
+impl Notification {
+    /// Summarizes one account's results, one line per game.
+    pub fn for_account(account: &str, results: &[GameResult]) -> Self {
+        let success = results.iter().all(GameResult::is_success);
+        let message = results.iter().map(line).collect::<Vec<_>>().join("\n");
+
+        Self {
+            account: account.to_string(),
+            title: format!(
+                "Check-in {} for {}",
+                if success { "succeeded" } else { "failed" },
+                account
+            ),
+            message,
+            success,
+        }
+    }
+
+    /// Only the problems the account's owner has to fix themselves, i.e.
+    /// expired cookies and captchas. `None` if there are none.
+    pub fn alert_for_account(account: &str, results: &[GameResult]) -> Option<Self> {
+        let lines: Vec<String> = results
+            .iter()
+            .filter(|result| needs_owner(result))
+            .map(line)
+            .collect();
+
+        (!lines.is_empty()).then(|| Self {
+            account: account.to_string(),
+            title: format!("Action needed for {}", account),
+            message: lines.join("\n"),
+            success: false,
+        })
+    }
+
+    /// A whole run's notifications as one, each account's lines under its
+    /// name. `None` if there are none.
+    pub fn combine(notifications: Vec<Self>) -> Option<Self> {
+        if notifications.len() <= 1 {
+            return notifications.into_iter().next();
+        }
+
+        let failed = notifications
+            .iter()
+            .filter(|notification| !notification.success)
+            .count();
+
+        Some(Self {
+            account: notifications
+                .iter()
+                .map(|notification| notification.account.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            title: match failed {
+                0 => format!("Check-in succeeded for {} accounts", notifications.len()),
+                failed => format!(
+                    "Check-in failed for {} of {} accounts",
+                    failed,
+                    notifications.len()
+                ),
+            },
+            message: notifications
+                .iter()
+                .map(|notification| format!("{}\n{}", notification.account, notification.message))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            success: failed == 0,
+        })
+    }
+
+    /// That the state file was corrupted and moved to `moved`, losing the
+    /// claims, devices and history it kept.
+    pub fn state_quarantined(moved: &Path) -> Self {
+        Self {
+            account: "state file".to_string(),
+            title: "State file corrupted".to_string(),
+            message: format!(
+                "Moved it to {} and started over without the check-in history and devices it kept",
+                moved.display()
+            ),
+            success: false,
+        }
+    }
+
+    /// `heading` and the message as texts of at most `limit` characters,
+    /// each under the heading. A combined message is split between accounts;
+    /// an account whose lines don't fit on their own loses the last of them
+    /// to an "…and N more" line.
+    pub fn split(&self, heading: &str, limit: usize) -> Vec<String> {
+        let room = limit.saturating_sub(heading.chars().count() + 1);
+        let mut texts = Vec::new();
+        let mut text = String::new();
+
+        for block in self.message.split("\n\n") {
+            let block = truncate(block, room);
+
+            if !text.is_empty() && text.chars().count() + 2 + block.chars().count() > room {
+                texts.push(format!("{}\n{}", heading, mem::take(&mut text)));
+            }
+
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+
+            text.push_str(&block);
+        }
+
+        texts.push(format!("{}\n{}", heading, text));
+        texts
+    }
+
+    pub fn append(&mut self, line: &str) {
+        self.message.push('\n');
+        self.message.push_str(line);
+    }
+}
+
+/// `block`'s first lines that fit in `room` characters along with a line
+/// counting the rest.
+fn truncate(block: &str, room: usize) -> String {
+    if block.chars().count() <= room {
+        return block.to_string();
+    }
+
+    let lines: Vec<&str> = block.lines().collect();
+    let mut kept = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let more = format!("…and {} more", lines.len() - index - 1);
+
+        if kept.chars().count() + line.chars().count() + more.chars().count() + 2 > room {
+            let more = format!("…and {} more", lines.len() - index);
+            return if kept.is_empty() {
+                more
+            } else {
+                format!("{}\n{}", kept, more)
+            };
+        }
+
+        if !kept.is_empty() {
+            kept.push('\n');
+        }
+
+        kept.push_str(line);
+    }
+
+    kept
+}
+
+fn line(result: &GameResult) -> String {
+    let game = match result.kind {
+        RewardKind::Daily => result.game.clone(),
+        RewardKind::Extra => format!("{} (event bonus)", result.game),
+    };
+
+    let progress = progress(result);
+
+    match result.status {
+        Status::Claimed => match &result.reward {
+            Some(reward) => format!("✓ {}: claimed {}{}", game, reward, progress),
+            None => format!("✓ {}: claimed{}", game, progress),
+        },
+        Status::AlreadyClaimed => format!("✓ {}: already claimed{}", game, progress),
+        Status::WouldClaim => format!("… {}: would claim", game),
+        Status::CaptchaRequired => match &result.captcha {
+            Some(captcha) => format!(
+                "✗ {}: captcha required, check in manually at {} or set a solved token as the account's captcha in the config",
+                game, captcha.page
+            ),
+            None => format!("✗ {}: captcha required", game),
+        },
+        Status::CookieExpired => format!(
+            "✗ {}: cookies expired, log in to HoYoLAB again and update the cookies of {}",
+            game, result.account
+        ),
+        Status::Failed => format!(
+            "✗ {}: {}",
+            game,
+            result.message.as_deref().unwrap_or("failed")
+        ),
+    }
+}
+
+/// E.g. " (day 27 of this month, 14-day streak)", for claimed daily rewards.
+fn progress(result: &GameResult) -> String {
+    let day = result
+        .sign_day
+        .map(|day| format!("day {} of this month", day));
+    let streak = result
+        .streak
+        .filter(|streak| *streak > 1)
+        .map(|streak| format!("{}-day streak", streak));
+    let parts: Vec<String> = day.into_iter().chain(streak).collect();
+
+    if result.kind == RewardKind::Extra || parts.is_empty() {
+        return String::new();
+    }
+
+    format!(" ({})", parts.join(", "))
+}
+
+fn needs_owner(result: &GameResult) -> bool {
+    matches!(
+        result.status,
+        Status::CaptchaRequired | Status::CookieExpired
+    )
+}
+
+impl Notifier {
+    fn send(&self, client: &Client, notification: &Notification) -> Result<(), Box<dyn Error>> {
+        let request = match self {
+            Self::Discord { webhook_url } => {
+                let heading = format!("**{}**", notification.title);
+
+                for text in notification.split(&heading, DISCORD_LIMIT) {
+                    client
+                        .post(webhook_url)
+                        .json(&json!({ "content": text }))
+                        .send()?
+                        .error_for_status()?;
+                }
+
+                return Ok(());
+            }
+            Self::Telegram { bot_token, chat_id } => {
+                let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
+
+                for text in notification.split(&notification.title, TELEGRAM_LIMIT) {
+                    client
+                        .post(&url)
+                        .json(&json!({ "chat_id": chat_id, "text": text }))
+                        .send()?
+                        .error_for_status()?;
+                }
+
+                return Ok(());
+            }
+            Self::Webhook { url, secret } => {
+                let body = serde_json::to_vec(notification)?;
+                let request = client.post(url).header(CONTENT_TYPE, "application/json");
+
+                match secret {
+                    Some(secret) => request.header("X-Hub-Signature-256", signature(secret, &body)),
+                    None => request,
+                }
+                .body(body)
+            }
+            Self::Email { to, sendmail } => return send_mail(to, sendmail, notification),
+            Self::Desktop => return desktop::send(notification),
+        };
+
+        request.send()?.error_for_status()?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Discord { .. } => "Discord",
+            Self::Telegram { .. } => "Telegram",
+            Self::Webhook { .. } => "webhook",
+            Self::Email { .. } => "email",
+            Self::Desktop => "desktop",
+        }
+    }
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body` under `secret`.
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+
+    let digest = mac.finalize().into_bytes();
+    format!("sha256={}", hex::encode(digest))
+}
+
+fn send_mail(
+    to: &str,
+    sendmail: &Option<String>,
+    notification: &Notification,
+) -> Result<(), Box<dyn Error>> {
+    let command = sendmail.as_deref().unwrap_or("sendmail");
+    let mut child = Command::new(command)
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    write!(
+        child.stdin.take().expect("Stdin is piped"),
+        "To: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+        to,
+        notification.title,
+        notification.message.replace('\n', "\r\n")
+    )?;
+
+    let status = child.wait()?;
+
+    if !status.success() {
+        return Err(format!("{} exited with {}", command, status).into());
+    }
+
+    Ok(())
+}
+
+/// Sends every notification to every notifier, in order.
+pub fn deliver(client: &Client, notifiers: &[Notifier], notifications: &[Notification]) {
+    for notification in notifications {
+        for notifier in notifiers {
+            debug!(
+                "Sending notification for {} via {}",
+                notification.account,
+                notifier.name()
+            );
+
+            if let Err(e) = notifier.send(client, notification) {
+                warn!("Failed to send {} notification: {}", notifier.name(), e);
+            }
+        }
+    }
+}
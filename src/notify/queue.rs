@@ -0,0 +1,35 @@
+use super::Notification;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+/// Collects notifications from any number of threads and hands them back in
+/// a deterministic order: by the position of the account in the config,
+/// then by the order they were queued for that account.
+#[derive(Default)]
+pub struct NotificationQueue {
+    entries: Mutex<Vec<(usize, u64, Notification)>>,
+    sequence: AtomicU64,
+}
+
+impl NotificationQueue {
+    pub fn push(&self, order: usize, notification: Notification) {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+
+        self.entries
+            .lock()
+            .expect("Notification queue poisoned")
+            .push((order, sequence, notification));
+    }
+
+    pub fn drain(&self) -> Vec<Notification> {
+        let mut entries =
+            std::mem::take(&mut *self.entries.lock().expect("Notification queue poisoned"));
+        entries.sort_by_key(|(order, sequence, _)| (*order, *sequence));
+        entries
+            .into_iter()
+            .map(|(_, _, notification)| notification)
+            .collect()
+    }
+}
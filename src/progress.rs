@@ -0,0 +1,93 @@
+//! The progress line shown while several accounts check in on a terminal.
+//! Console logs are written around it through `Writer`.
+
+use crate::report::GameResult;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    io::{self, Write},
+    sync::Mutex,
+};
+
+/// The bar on screen, if any, for `Writer` to clear before each log line.
+static BAR: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+pub struct Progress {
+    bar: ProgressBar,
+    account: String,
+    done: usize,
+    failed: usize,
+}
+
+impl Progress {
+    pub fn start(accounts: usize) -> Self {
+        let bar = ProgressBar::new(accounts as u64).with_style(
+            ProgressStyle::with_template("{bar:20} {pos}/{len} accounts, {msg}")
+                .expect("Valid progress template"),
+        );
+        *BAR.lock().expect("Progress lock poisoned") = Some(bar.clone());
+
+        Self {
+            bar,
+            account: String::new(),
+            done: 0,
+            failed: 0,
+        }
+    }
+
+    /// Moves on to the next account.
+    pub fn account(&mut self, name: &str) {
+        self.account = name.to_string();
+        self.bar.inc(1);
+        self.show(None);
+    }
+
+    pub fn game(&self, name: &str) {
+        self.show(Some(name));
+    }
+
+    /// Counts the finished check-ins of the current account.
+    pub fn finished(&mut self, results: &[GameResult]) {
+        let failed = results.iter().filter(|result| !result.is_success()).count();
+
+        self.done += results.len() - failed;
+        self.failed += failed;
+        self.show(None);
+    }
+
+    fn show(&self, game: Option<&str>) {
+        let current = match game {
+            Some(game) => format!("{}: {}", self.account, game),
+            None => self.account.clone(),
+        };
+
+        self.bar.set_message(format!(
+            "{} done, {} failed · {}",
+            self.done, self.failed, current
+        ));
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        BAR.lock().expect("Progress lock poisoned").take();
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Writes console logs above the progress line instead of through it.
+pub struct Writer<W>(pub W);
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let bar = BAR.lock().expect("Progress lock poisoned").clone();
+
+        match bar {
+            Some(bar) => bar.suspend(|| self.0.write(buf)),
+            None => self.0.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
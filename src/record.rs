@@ -0,0 +1,61 @@
+use crate::{client::HttpClient, games::Genshin, CheckinError, HoyolabCheckin, SignResponse};
+use http::Method;
+use serde::Deserialize;
+
+/// The account's game record cards, one per game it has played.
+const URL_CARDS: &str = "https://bbs-api-os.hoyolab.com/game_record/card/wapi/getGameRecordCard";
+
+#[derive(Deserialize)]
+struct CardsData {
+    #[serde(default)]
+    list: Vec<Card>,
+}
+
+#[derive(Deserialize)]
+struct Card {
+    game_id: u32,
+    #[serde(default = "has_role")]
+    has_role: bool,
+}
+
+fn has_role() -> bool {
+    true
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// `Game::hoyolab_id` of every game the account has a character in.
+    /// `None` without the `ltuid` cookie to look the account up by.
+    pub async fn played_games(&self) -> Result<Option<Vec<u32>>, CheckinError> {
+        let Some(uid) = ["ltuid_v2", "ltuid"]
+            .iter()
+            .find_map(|name| self.account.cookies.get(*name))
+        else {
+            return Ok(None);
+        };
+
+        let request = self.request(
+            &Genshin,
+            Method::GET,
+            URL_CARDS,
+            &[("uid", uid.as_str())],
+            Vec::new(),
+        )?;
+        let response: SignResponse<CardsData> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        Ok(Some(
+            response
+                .data
+                .map(|data| data.list)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|card| card.has_role)
+                .map(|card| card.game_id)
+                .collect(),
+        ))
+    }
+}
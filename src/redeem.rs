@@ -0,0 +1,240 @@
+use crate::{
+    client::HttpClient, clock, games::Game, retcode, CheckinError, HoyolabCheckin, SignResponse,
+};
+use http::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Lists the account's characters per game and server.
+const ROLES_URL: &str = "https://api-os-takumi.hoyolab.com/binding/api/getUserGameRolesByLtoken";
+
+/// HoYoverse rejects redemptions sent less than about five seconds apart.
+const COOLDOWN: Duration = Duration::from_secs(6);
+
+#[derive(Deserialize)]
+struct RolesData {
+    #[serde(default)]
+    list: Vec<Role>,
+}
+
+/// A character on one server of a game.
+#[derive(Deserialize)]
+pub struct Role {
+    pub game_uid: String,
+    pub region: String,
+    #[serde(default)]
+    level: u32,
+}
+
+/// A JSON list of currently active codes, checked every run so new codes
+/// are redeemed without anyone having to add them to `gift_codes`.
+#[derive(Deserialize)]
+pub struct GiftCodeFeed {
+    /// `{game}` is replaced with the game id. The response is either a list
+    /// of codes (strings, or objects with a `code` field), an object with
+    /// such a list under `codes`, or an object of such lists by game id.
+    url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedeemStatus {
+    Redeemed,
+    /// Redeemed earlier, by this run or another.
+    AlreadyRedeemed,
+    /// Expired, mistyped, or for another game or region.
+    Invalid,
+    Failed,
+}
+
+#[derive(Serialize)]
+pub struct Redemption {
+    pub account: String,
+    pub game: String,
+    pub uid: String,
+    pub code: String,
+    pub status: RedeemStatus,
+    pub message: Option<String>,
+    #[serde(skip)]
+    pub error: Option<CheckinError>,
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// The character codes are redeemed on: the account's configured UID for
+    /// the game, or else its highest-level character.
+    pub async fn role(&self, game: &dyn Game) -> Result<Option<Role>, CheckinError> {
+        let request = self.request(
+            game,
+            Method::GET,
+            ROLES_URL,
+            &[("game_biz", game.game_biz())],
+            Vec::new(),
+        )?;
+        let response: SignResponse<RolesData> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        let roles = response.data.map(|data| data.list).unwrap_or_default();
+
+        Ok(match self.account.uids.get(game.id()) {
+            Some(uid) => roles.into_iter().find(|role| role.game_uid == *uid),
+            None => roles.into_iter().max_by_key(|role| role.level),
+        })
+    }
+
+    /// Redeems each code on the game, waiting out the cooldown in between.
+    /// Fails only if the character to redeem on cannot be looked up.
+    pub async fn redeem(
+        &self,
+        game: &dyn Game,
+        codes: &[String],
+    ) -> Result<Vec<Redemption>, CheckinError> {
+        let Some(url) = game.url_redeem() else {
+            warn!("{} has no gift code redemption", game.name());
+            return Ok(Vec::new());
+        };
+
+        let Some(role) = self.role(game).await? else {
+            warn!("No {} character found to redeem codes on", game.name());
+            return Ok(Vec::new());
+        };
+
+        let mut redemptions = Vec::new();
+
+        for (index, code) in codes.iter().enumerate() {
+            if index > 0 {
+                self.client.sleep(COOLDOWN).await;
+            }
+
+            let mut outcome = self.redeem_code(game, url, &role, code).await;
+
+            if let Err(CheckinError::Api {
+                retcode: retcode::REDEEM_COOLDOWN,
+                ..
+            }) = outcome
+            {
+                // Still cooling down from a redemption elsewhere.
+                self.client.sleep(COOLDOWN).await;
+                outcome = self.redeem_code(game, url, &role, code).await;
+            }
+
+            match &outcome {
+                Ok(RedeemStatus::Redeemed) => info!("Redeemed {}", code),
+                Ok(RedeemStatus::AlreadyRedeemed) => info!("{} was already redeemed", code),
+                Ok(RedeemStatus::Invalid) => warn!("{} is invalid or expired", code),
+                Ok(RedeemStatus::Failed) => {}
+                Err(e) => warn!("Failed to redeem {}: {}", code, e),
+            }
+
+            let (status, error) = match outcome {
+                Ok(status) => (status, None),
+                Err(e) => (RedeemStatus::Failed, Some(e)),
+            };
+
+            redemptions.push(Redemption {
+                account: self.account.name.clone(),
+                game: game.name().to_string(),
+                uid: role.game_uid.clone(),
+                code: code.clone(),
+                status,
+                message: error.as_ref().map(ToString::to_string),
+                error,
+            });
+        }
+
+        Ok(redemptions)
+    }
+
+    async fn redeem_code(
+        &self,
+        game: &dyn Game,
+        url: &str,
+        role: &Role,
+        code: &str,
+    ) -> Result<RedeemStatus, CheckinError> {
+        let timestamp = (clock::unix_now() * 1000).to_string();
+        let request = self.request(
+            game,
+            Method::GET,
+            url,
+            &[
+                ("uid", &role.game_uid),
+                ("region", &role.region),
+                ("game_biz", game.game_biz()),
+                ("cdkey", code),
+                ("lang", self.lang().split('-').next().unwrap_or("en")),
+                ("sLangKey", self.lang()),
+                ("t", &timestamp),
+            ],
+            Vec::new(),
+        )?;
+        let response: SignResponse<serde_json::Value> = self.send(request).await?;
+
+        match response.retcode.unwrap_or(0) {
+            0 => Ok(RedeemStatus::Redeemed),
+            // "Redemption code already in use" / "already redeemed".
+            -2017 | -2018 => Ok(RedeemStatus::AlreadyRedeemed),
+            // Expired, invalid, or not yet active.
+            -2001 | -2003 | -2006 | -1065 => Ok(RedeemStatus::Invalid),
+            -1071 => Err(CheckinError::Api {
+                retcode: -1071,
+                message: Some(
+                    "Not logged in, redeeming needs cookie_token_v2 and account_id_v2 in the account's cookies"
+                        .to_string(),
+                ),
+            }),
+            retcode => Err(CheckinError::api(retcode, response.message)),
+        }
+    }
+}
+
+impl GiftCodeFeed {
+    /// The feed's codes for the game, in the order listed.
+    pub async fn fetch<C: HttpClient>(
+        &self,
+        client: &C,
+        game: &dyn Game,
+    ) -> Result<Vec<String>, String> {
+        let url = self.url.replace("{game}", game.id());
+        let failed = |e: &dyn std::fmt::Display| format!("Failed to fetch {}: {}", url, e);
+
+        let request = http::Request::builder()
+            .method(Method::GET)
+            .uri(&url)
+            .body(Vec::new())
+            .map_err(|e| failed(&e))?;
+        let response = client.execute(request).await.map_err(|e| failed(&e))?;
+
+        if !response.status().is_success() {
+            return Err(failed(&response.status()));
+        }
+
+        let feed: Value = serde_json::from_slice(response.body()).map_err(|e| failed(&e))?;
+        let list = match &feed {
+            Value::Object(object) => object
+                .get("codes")
+                .or_else(|| object.get(game.id()))
+                .unwrap_or(&Value::Null),
+            list => list,
+        };
+
+        let Value::Array(list) = list else {
+            return Err(failed(&"no list of codes in the response"));
+        };
+
+        Ok(list
+            .iter()
+            .filter_map(|entry| match entry {
+                Value::String(code) => Some(code.as_str()),
+                entry => entry.get("code").and_then(Value::as_str),
+            })
+            .map(|code| code.trim().to_string())
+            .filter(|code| !code.is_empty())
+            .collect())
+    }
+}
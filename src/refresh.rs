@@ -0,0 +1,105 @@
+// Links are only handed out when the server can answer them.
+#![cfg_attr(not(feature = "server"), allow(dead_code))]
+
+use crate::{clock, config_file, cookies, validate};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+use tracing::info;
+
+/// How long a refresh link stays usable.
+const LINK_LIFETIME: u64 = 24 * 60 * 60;
+
+/// Single-use links to the server's cookie refresh page, handed out with
+/// cookie-expired alerts so new cookies can be pasted in from a browser.
+/// Links are kept in memory and die with the daemon.
+pub struct CookieRefresh {
+    /// Where the server is reachable from outside, e.g. `https://checkin.example.com`.
+    public_url: String,
+    config_path: PathBuf,
+    profile: Option<String>,
+    /// Token → (account, expiry).
+    links: Mutex<HashMap<String, (String, u64)>>,
+    updated: AtomicBool,
+}
+
+impl CookieRefresh {
+    pub fn new(public_url: &str, config_path: &Path, profile: Option<&str>) -> Self {
+        Self {
+            public_url: public_url.trim_end_matches('/').to_string(),
+            config_path: config_path.to_path_buf(),
+            profile: profile.map(str::to_string),
+            links: Mutex::default(),
+            updated: AtomicBool::new(false),
+        }
+    }
+
+    /// Creates a link that lets whoever holds it replace `account`'s cookies once.
+    pub fn link(&self, account: &str) -> Result<String, String> {
+        let token = random_token().map_err(|e| format!("Failed to generate token: {}", e))?;
+        let now = clock::unix_now();
+        let mut links = self.links.lock().expect("Refresh links poisoned");
+
+        links.retain(|_, (_, expires)| *expires > now);
+        links.insert(token.clone(), (account.to_string(), now + LINK_LIFETIME));
+
+        Ok(format!("{}/refresh?token={}", self.public_url, token))
+    }
+
+    /// The account a token may refresh, if it is still valid.
+    pub fn account(&self, token: &str) -> Option<String> {
+        let links = self.links.lock().expect("Refresh links poisoned");
+
+        links
+            .get(token)
+            .filter(|(_, expires)| *expires > clock::unix_now())
+            .map(|(account, _)| account.clone())
+    }
+
+    /// Stores the cookies from a pasted `Cookie` header in the config file
+    /// and uses up the token.
+    pub fn submit(&self, token: &str, header: &str) -> Result<String, String> {
+        let account = self
+            .account(token)
+            .ok_or("This link has expired or was already used")?;
+        let cookies: BTreeMap<String, String> = cookies::parse_cookie_header(header).collect();
+
+        if let Some(reason) =
+            validate::malformed(&cookies.clone().into_iter().collect::<HashMap<_, _>>())
+        {
+            return Err(format!("These cookies look malformed: {}", reason));
+        }
+
+        config_file::save_cookies(
+            &self.config_path,
+            self.profile.as_deref(),
+            &account,
+            &cookies,
+        )?;
+        self.links
+            .lock()
+            .expect("Refresh links poisoned")
+            .remove(token);
+        self.updated.store(true, Ordering::SeqCst);
+
+        info!("Cookies for {} were refreshed through the server", account);
+        Ok(account)
+    }
+
+    /// Whether cookies were refreshed since the last call.
+    pub fn take_updated(&self) -> bool {
+        self.updated.swap(false, Ordering::SeqCst)
+    }
+}
+
+fn random_token() -> std::io::Result<String> {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).map_err(std::io::Error::other)?;
+
+    Ok(hex::encode(bytes))
+}
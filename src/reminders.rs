@@ -0,0 +1,56 @@
+use crate::{client::HttpClient, games::Genshin, CheckinError, HoyolabCheckin, SignResponse};
+use http::Method;
+use serde::Deserialize;
+
+/// Genshin's "Birthday Wishes" web event lists the characters whose birthday
+/// is today.
+const BIRTHDAY_URL: &str = "https://sg-hk4e-api.hoyolab.com/event/birthdaystar/account/index";
+const BIRTHDAY_ACTIVITY_ID: &str = "20220301153521";
+
+#[derive(Deserialize)]
+struct BirthdayData {
+    #[serde(default)]
+    role: Vec<BirthdayRole>,
+}
+
+#[derive(Deserialize)]
+struct BirthdayRole {
+    name: String,
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// Names of the characters celebrating their birthday today.
+    pub async fn birthdays(&self) -> Result<Vec<String>, CheckinError> {
+        let request = self.request(
+            &Genshin,
+            Method::GET,
+            BIRTHDAY_URL,
+            &[("lang", self.lang()), ("activity_id", BIRTHDAY_ACTIVITY_ID)],
+            Vec::new(),
+        )?;
+        let response: SignResponse<BirthdayData> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        Ok(response
+            .data
+            .map(|data| data.role.into_iter().map(|role| role.name).collect())
+            .unwrap_or_default())
+    }
+}
+
+/// A gentle line for the daily notification, or `None` if nobody is celebrating.
+pub fn birthday_message(names: &[String]) -> Option<String> {
+    match names {
+        [] => None,
+        [name] => Some(format!("🎂 Today is {}'s birthday!", name)),
+        [names @ .., last] => Some(format!(
+            "🎂 Today is the birthday of {} and {}!",
+            names.join(", "),
+            last
+        )),
+    }
+}
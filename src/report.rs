@@ -0,0 +1,287 @@
+use crate::{clock, games::Game, CheckinError};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// The reward was claimed during this run.
+    Claimed,
+    /// The reward had already been claimed earlier today.
+    AlreadyClaimed,
+    /// The reward is unclaimed, but this is a dry run.
+    WouldClaim,
+    /// HoYoLAB asked for a Geetest captcha before signing.
+    CaptchaRequired,
+    /// The account's cookies need to be refreshed.
+    CookieExpired,
+    Failed,
+}
+
+impl Status {
+    /// Short form for tables.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Claimed => "✓ claimed",
+            Self::AlreadyClaimed => "✓ done",
+            Self::WouldClaim => "… would claim",
+            Self::CaptchaRequired => "✗ captcha",
+            Self::CookieExpired => "✗ cookies",
+            Self::Failed => "✗ failed",
+        }
+    }
+
+    /// ANSI color code: green for success, yellow for what a person has to
+    /// look at, red for failures.
+    fn color(self) -> &'static str {
+        match self {
+            Self::Claimed | Self::AlreadyClaimed => "32",
+            Self::WouldClaim | Self::CaptchaRequired | Self::CookieExpired => "33",
+            Self::Failed => "31",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardKind {
+    /// The regular daily check-in reward.
+    Daily,
+    /// A limited-time bonus offered during version events.
+    Extra,
+}
+
+/// The Geetest challenge HoYoLAB returned, for solving it out of band.
+#[derive(Serialize)]
+pub struct CaptchaChallenge {
+    pub gt: Option<String>,
+    pub challenge: Option<String>,
+    /// The web check-in page, for checking in by hand instead.
+    pub page: String,
+}
+
+/// Outcome of checking in one account on one game.
+#[derive(Serialize)]
+pub struct GameResult {
+    pub account: String,
+    pub game: String,
+    pub kind: RewardKind,
+    pub status: Status,
+    pub retcode: Option<i32>,
+    pub message: Option<String>,
+    /// What was claimed, e.g. "Primogem ×60".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captcha: Option<CaptchaChallenge>,
+    /// Days checked in this month as reported by HoYoLAB, today included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sign_day: Option<u32>,
+    /// Consecutive server days checked in, today included. Only known with a
+    /// state file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streak: Option<u32>,
+    /// HoYoLAB server day (UTC+8) the attempt counts towards.
+    pub server_date: String,
+    pub started_at: String,
+    pub finished_at: String,
+    /// How long the check-in took, requests and waits between them included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip)]
+    pub error: Option<CheckinError>,
+}
+
+impl GameResult {
+    pub fn new(
+        account: &str,
+        game: &dyn Game,
+        started_at: u64,
+        outcome: Result<Status, CheckinError>,
+    ) -> Self {
+        let (status, error) = match outcome {
+            Ok(status) => (status, None),
+            Err(e @ CheckinError::CaptchaRequired { .. }) => (Status::CaptchaRequired, Some(e)),
+            Err(e @ CheckinError::CookieExpired { .. }) => (Status::CookieExpired, Some(e)),
+            Err(e) => (Status::Failed, Some(e)),
+        };
+
+        Self {
+            account: account.to_string(),
+            game: game.name().to_string(),
+            kind: RewardKind::Daily,
+            status,
+            retcode: error.as_ref().and_then(CheckinError::retcode),
+            message: error.as_ref().map(ToString::to_string),
+            reward: None,
+            captcha: match &error {
+                Some(CheckinError::CaptchaRequired { gt, challenge }) => Some(CaptchaChallenge {
+                    gt: gt.clone(),
+                    challenge: challenge.clone(),
+                    page: game.url_page().to_string(),
+                }),
+                _ => None,
+            },
+            sign_day: None,
+            streak: None,
+            server_date: clock::server_date(started_at),
+            started_at: clock::rfc3339(started_at),
+            finished_at: clock::rfc3339(clock::unix_now()),
+            duration_ms: None,
+            error,
+        }
+    }
+
+    /// Marks this result as being for the event bonus rather than the daily reward.
+    pub fn extra(mut self) -> Self {
+        self.kind = RewardKind::Extra;
+        self
+    }
+
+    pub fn reward(mut self, reward: Option<String>) -> Self {
+        self.reward = reward;
+        self
+    }
+
+    pub fn sign_day(mut self, sign_day: Option<u32>) -> Self {
+        self.sign_day = sign_day;
+        self
+    }
+
+    /// Sets the duration from when the check-in started, in `clock::unix_millis`.
+    pub fn timed(mut self, started_ms: u64) -> Self {
+        self.duration_ms = Some(clock::unix_millis().saturating_sub(started_ms));
+        self
+    }
+
+    pub fn is_success(&self) -> bool {
+        !matches!(
+            self.status,
+            Status::Failed | Status::CaptchaRequired | Status::CookieExpired
+        )
+    }
+}
+
+/// A whole run, as written by `--output-file` for other automation to read.
+#[derive(Serialize)]
+struct RunResults<'a> {
+    started_at: String,
+    finished_at: String,
+    dry_run: bool,
+    success: bool,
+    results: &'a [GameResult],
+}
+
+/// Writes the run's results to `path` as one JSON document. Goes through a
+/// temporary file so readers never see it half written.
+pub fn write(path: &Path, started_at: u64, dry_run: bool, report: &[GameResult]) -> io::Result<()> {
+    let data = serde_json::to_vec_pretty(&RunResults {
+        started_at: clock::rfc3339(started_at),
+        finished_at: clock::rfc3339(clock::unix_now()),
+        dry_run,
+        success: report.iter().all(GameResult::is_success),
+        results: report,
+    })?;
+
+    let mut temp = path.to_path_buf().into_os_string();
+    temp.push(".tmp");
+
+    fs::write(&temp, data)?;
+    fs::rename(&temp, path)
+}
+
+/// The account × game grid of the run's results, header row first. Each cell
+/// has its text and the status to color it by.
+fn grid(report: &[GameResult]) -> Vec<Vec<(String, Option<Status>)>> {
+    let mut accounts: Vec<&str> = Vec::new();
+    let mut columns: Vec<(&str, RewardKind)> = Vec::new();
+
+    for result in report {
+        if !accounts.contains(&result.account.as_str()) {
+            accounts.push(&result.account);
+        }
+
+        if !columns.contains(&(result.game.as_str(), result.kind)) {
+            columns.push((&result.game, result.kind));
+        }
+    }
+
+    let mut rows = vec![std::iter::once(("Account".to_string(), None))
+        .chain(columns.iter().map(|(game, kind)| match kind {
+            RewardKind::Daily => (game.to_string(), None),
+            RewardKind::Extra => (format!("{} (bonus)", game), None),
+        }))
+        .collect::<Vec<_>>()];
+
+    for account in &accounts {
+        let mut row = vec![(account.to_string(), None)];
+
+        for (game, kind) in &columns {
+            let status = report
+                .iter()
+                .find(|r| r.account == *account && r.game == *game && r.kind == *kind)
+                .map(|result| result.status);
+
+            row.push((status.map_or("-", Status::label).to_string(), status));
+        }
+
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Renders an account × game grid of the run's results, with each status in
+/// its color when `color` is set.
+pub fn summary_table(report: &[GameResult], color: bool) -> String {
+    let rows = grid(report);
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].0.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|((cell, status), width)| {
+                    let padding = " ".repeat(width - cell.chars().count());
+
+                    match status.filter(|_| color) {
+                        Some(status) => {
+                            format!("\x1b[{}m{}\x1b[0m{}", status.color(), cell, padding)
+                        }
+                        None => format!("{}{}", cell, padding),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The account × game grid as a Markdown table.
+pub fn markdown_table(report: &[GameResult]) -> String {
+    let rows = grid(report);
+    let line = |row: &[(String, Option<Status>)]| {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|(cell, _)| cell.replace('|', "\\|"))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![
+        line(&rows[0]),
+        format!("|{}", " --- |".repeat(rows[0].len())),
+    ];
+    lines.extend(rows[1..].iter().map(|row| line(row)));
+    lines.join("\n")
+}
@@ -0,0 +1,110 @@
+use crate::{
+    client::HttpClient, games::Game, CheckinError, HoyolabCheckin, SignRequest, SignResponse,
+};
+use http::Method;
+use serde::Deserialize;
+use tracing::{debug, info};
+
+/// How many days of the month can still be made up, and at what price.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ResignInfo {
+    /// Days of the month so far that were never checked in.
+    sign_cnt_missed: u32,
+    resign_cnt_daily: u32,
+    resign_limit_daily: u32,
+    resign_cnt_monthly: u32,
+    resign_limit_monthly: u32,
+    /// The account's HoYoLAB coins, and what one make-up costs.
+    coin_cnt: u32,
+    coin_cost: u32,
+}
+
+impl ResignInfo {
+    /// Make-ups the limits and coins still allow today.
+    fn affordable(&self) -> u32 {
+        let coins = match self.coin_cost {
+            0 => u32::MAX,
+            cost => self.coin_cnt / cost,
+        };
+
+        self.sign_cnt_missed
+            .min(
+                self.resign_limit_daily
+                    .saturating_sub(self.resign_cnt_daily),
+            )
+            .min(
+                self.resign_limit_monthly
+                    .saturating_sub(self.resign_cnt_monthly),
+            )
+            .min(coins)
+    }
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// Spends HoYoLAB coins on the make-up check-in to recover days missed
+    /// this month, as many as the daily and monthly limits allow. Only works
+    /// once today's check-in is done. Returns how many days were made up.
+    pub async fn makeup_sign(&self, game: &dyn Game) -> Result<u32, CheckinError> {
+        let (Some(url_info), Some(url_resign)) = (game.url_resign_info(), game.url_resign()) else {
+            return Ok(0);
+        };
+
+        let request = self.request(
+            game,
+            Method::GET,
+            url_info,
+            &[("lang", self.lang()), ("act_id", game.act_id())],
+            Vec::new(),
+        )?;
+        let response: SignResponse<ResignInfo> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        let info = response.data.unwrap_or_default();
+        let affordable = info.affordable();
+
+        debug!(
+            "{} missed days, {} can be made up with {} coins",
+            info.sign_cnt_missed, affordable, info.coin_cnt
+        );
+
+        let data = serde_json::to_vec(&SignRequest {
+            act_id: game.act_id().to_string(),
+        })
+        .map_err(|e| CheckinError::UnexpectedResponse(e.to_string()))?;
+        let mut made_up = 0;
+
+        while made_up < affordable {
+            let request = self.request(
+                game,
+                Method::POST,
+                url_resign,
+                &[("lang", self.lang())],
+                data.clone(),
+            )?;
+            let response: SignResponse<serde_json::Value> = self.send(request).await?;
+            let return_code = response.retcode.unwrap_or(0);
+
+            if return_code != 0 {
+                // Days made up so far are still worth reporting.
+                if made_up > 0 {
+                    break;
+                }
+
+                return Err(CheckinError::api(return_code, response.message));
+            }
+
+            made_up += 1;
+        }
+
+        if made_up > 0 {
+            info!("Made up {} missed days", made_up);
+        }
+
+        Ok(made_up)
+    }
+}
@@ -0,0 +1,48 @@
+//! HoYoLAB return codes with a known meaning. The API's own messages are
+//! often translated or vague, so these are shown instead where they apply.
+
+/// "Traveler, you've already checked in today~"
+pub const ALREADY_SIGNED: i32 = -5003;
+/// "Please log in", from the check-in endpoints.
+pub const NOT_LOGGED_IN: i32 = -100;
+/// "Not logged in", from the game record endpoints.
+pub const NOT_LOGGED_IN_RECORD: i32 = 10001;
+/// "Abnormal request", returned instead of a challenge by some endpoints.
+pub const ABNORMAL_REQUEST: i32 = 1034;
+/// "Already signed in", from the community sign-in.
+pub const COMMUNITY_SIGNED: i32 = 1008;
+/// Too many redemptions in a short time.
+pub const REDEEM_COOLDOWN: i32 = -2016;
+
+const KNOWN: &[(i32, &str)] = &[
+    (ALREADY_SIGNED, "Already checked in today"),
+    (
+        NOT_LOGGED_IN,
+        "Not logged in, the cookies are invalid or expired",
+    ),
+    (
+        NOT_LOGGED_IN_RECORD,
+        "Not logged in, the cookies are invalid or expired",
+    ),
+    (-10001, "Invalid request, the API may have changed"),
+    (-10002, "No game character found on this account"),
+    (ABNORMAL_REQUEST, "Abnormal request, a captcha is required"),
+    (COMMUNITY_SIGNED, "Already signed in to the community"),
+    (-1, "HoYoLAB system busy, try again later"),
+    (-5001, "The check-in event is not running"),
+    (REDEEM_COOLDOWN, "Redeeming too often, wait a few seconds"),
+    (-2017, "Code already redeemed"),
+    (-2018, "Code already redeemed"),
+    (-2001, "Code expired"),
+    (-2003, "Invalid code"),
+    (-2006, "Code fully redeemed"),
+    (-1065, "Code not active yet"),
+];
+
+/// What `retcode` means, if it is one of the known ones.
+pub fn meaning(retcode: i32) -> Option<&'static str> {
+    KNOWN
+        .iter()
+        .find(|(known, _)| *known == retcode)
+        .map(|(_, meaning)| *meaning)
+}
@@ -0,0 +1,153 @@
+use crate::{client::HttpClient, games::Game, CheckinError, HoyolabCheckin, SignResponse};
+use http::Method;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::{info, warn};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+#[derive(Deserialize)]
+struct HomeData {
+    month: Option<u32>,
+    #[serde(default)]
+    awards: Vec<Award>,
+}
+
+/// One day's check-in reward.
+#[derive(Deserialize)]
+struct Award {
+    name: String,
+    cnt: u32,
+}
+
+impl fmt::Display for Award {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ×{}", self.name, self.cnt)
+    }
+}
+
+/// A game's rewards for the month, for the `rewards` command.
+#[derive(Serialize)]
+pub struct Calendar {
+    account: String,
+    game: String,
+    month: Option<u32>,
+    days: Vec<CalendarDay>,
+}
+
+#[derive(Serialize)]
+struct CalendarDay {
+    day: usize,
+    reward: String,
+    claimed: bool,
+}
+
+impl fmt::Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rewards for {}", self.game, self.account)?;
+
+        if let Some(month) = self
+            .month
+            .and_then(|month| MONTHS.get((month as usize).checked_sub(1)?))
+        {
+            write!(f, " in {}", month)?;
+        }
+
+        for day in &self.days {
+            write!(
+                f,
+                "\n{} {:>2}  {}",
+                if day.claimed { '✓' } else { ' ' },
+                day.day,
+                day.reward
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    async fn home(&self, game: &dyn Game) -> Result<HomeData, CheckinError> {
+        let request = self.request(
+            game,
+            Method::GET,
+            game.url_home(),
+            &[("lang", self.lang()), ("act_id", game.act_id())],
+            Vec::new(),
+        )?;
+        let response: SignResponse<HomeData> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        response
+            .data
+            .ok_or_else(|| CheckinError::UnexpectedResponse("no reward calendar".to_string()))
+    }
+
+    /// The month's rewards, with the days signed so far marked as claimed.
+    pub async fn calendar(&self, game: &dyn Game) -> Result<Calendar, CheckinError> {
+        let signed = self.info(game).await?.total_sign_day as usize;
+        let home = self.home(game).await?;
+
+        Ok(Calendar {
+            account: self.account.name.clone(),
+            game: game.name().to_string(),
+            month: home.month,
+            days: home
+                .awards
+                .iter()
+                .enumerate()
+                .map(|(index, award)| CalendarDay {
+                    day: index + 1,
+                    reward: award.to_string(),
+                    claimed: index < signed,
+                })
+                .collect(),
+        })
+    }
+
+    /// What today's sign claimed, e.g. "Primogem ×60". It is only shown to
+    /// the user, so failing to look it up is not an error.
+    pub async fn claimed_reward(&self, game: &dyn Game) -> Option<String> {
+        let lookup = async {
+            let signed = self.info(game).await?.total_sign_day;
+            let mut awards = self.home(game).await?.awards;
+
+            Ok::<_, CheckinError>(
+                signed
+                    .checked_sub(1)
+                    .filter(|&day| (day as usize) < awards.len())
+                    .map(|day| awards.swap_remove(day as usize)),
+            )
+        };
+
+        match lookup.await {
+            Ok(Some(award)) => {
+                info!("Claimed {}", award);
+                Some(award.to_string())
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Failed to look up the claimed reward: {}", e);
+                None
+            }
+        }
+    }
+}
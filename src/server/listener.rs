@@ -0,0 +1,183 @@
+use super::{Control, ServerConfig, Trigger};
+use crate::{metrics::Metrics, refresh::CookieRefresh};
+use serde_json::{json, Value};
+use std::{
+    borrow::Cow,
+    io::{Cursor, Read},
+    sync::Arc,
+    thread,
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{error, info};
+
+type Page = Response<Cursor<Vec<u8>>>;
+
+/// Serves the HTTP endpoints on a background thread for as long as the
+/// daemon runs.
+pub fn spawn(
+    config: &ServerConfig,
+    metrics: Arc<Metrics>,
+    refresh: Option<Arc<CookieRefresh>>,
+    control: Arc<Control>,
+) {
+    let server = match Server::http(&config.listen) {
+        Ok(server) => server,
+        Err(e) => {
+            error!("Failed to listen on {}: {}", config.listen, e);
+            return;
+        }
+    };
+
+    info!("Listening on {}", config.listen);
+
+    let token = config.api_token.clone();
+
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let url = request.url().to_string();
+            let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+            let response = match (request.method(), path, &refresh) {
+                (Method::Get, "/metrics", _) => Response::from_string(metrics.render())
+                    .with_header(content_type("text/plain; version=0.0.4")),
+                (Method::Get, "/refresh", Some(refresh)) => {
+                    refresh_page(refresh, &form_value(query, "token"))
+                }
+                (Method::Post, "/refresh", Some(refresh)) => {
+                    let mut body = String::new();
+                    let _ = request
+                        .as_reader()
+                        .take(64 * 1024)
+                        .read_to_string(&mut body);
+
+                    refresh_submit(
+                        refresh,
+                        &form_value(&body, "token"),
+                        &form_value(&body, "cookies"),
+                    )
+                }
+                (_, path, _) if is_run(path) && token.is_none() => json_page(
+                    403,
+                    &json!({ "error": "Set api_token in the server config to trigger runs" }),
+                ),
+                (_, path, _)
+                    if (path == "/status" || is_run(path))
+                        && !authorized(&request, token.as_deref()) =>
+                {
+                    json_page(401, &json!({ "error": "Unauthorized" }))
+                }
+                (Method::Get, "/status", _) => json_page(200, &control.status()),
+                (Method::Post, "/run", _) => {
+                    control.trigger(Trigger::All);
+                    json_page(202, &json!({ "queued": "all" }))
+                }
+                (Method::Post, path, _) if path.starts_with("/run/") => {
+                    let account = decode(&path["/run/".len()..]);
+                    control.trigger(Trigger::Account(account.clone()));
+                    json_page(202, &json!({ "queued": account }))
+                }
+                _ => Response::from_string("Not found").with_status_code(404),
+            };
+
+            let _ = request.respond(response);
+        }
+    });
+}
+
+fn refresh_page(refresh: &CookieRefresh, token: &str) -> Page {
+    let Some(account) = refresh.account(token) else {
+        return html(403, "<p>This link has expired or was already used.</p>");
+    };
+
+    html(
+        200,
+        &format!(
+            "<h1>Refresh cookies for {}</h1>\
+             <p>Log in to HoYoLAB, copy the <code>Cookie</code> request header of any \
+             hoyolab.com request from the browser's developer tools and paste it below.</p>\
+             <form method=\"post\" action=\"/refresh\">\
+             <input type=\"hidden\" name=\"token\" value=\"{}\">\
+             <textarea name=\"cookies\" rows=\"8\" cols=\"80\" required></textarea><br>\
+             <button type=\"submit\">Save</button></form>",
+            escape(&account),
+            escape(token)
+        ),
+    )
+}
+
+fn refresh_submit(refresh: &CookieRefresh, token: &str, cookies: &str) -> Page {
+    match refresh.submit(token, cookies) {
+        Ok(account) => html(
+            200,
+            &format!(
+                "<p>Cookies for {} saved. They will be used from the next run on.</p>",
+                escape(&account)
+            ),
+        ),
+        Err(e) => html(400, &format!("<p>{}</p>", escape(&e))),
+    }
+}
+
+/// Whether the path triggers a run, `/run` or `/run/<account>`, and not
+/// merely starts like it.
+fn is_run(path: &str) -> bool {
+    path == "/run" || path.starts_with("/run/")
+}
+
+fn authorized(request: &Request, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+
+    request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && header.value.as_str().strip_prefix("Bearer ") == Some(token)
+    })
+}
+
+fn json_page(status: u16, body: &Value) -> Page {
+    Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(content_type("application/json"))
+}
+
+fn html(status: u16, body: &str) -> Page {
+    Response::from_string(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>hoyo-checkin-rs</title></head><body>{}</body></html>",
+        body
+    ))
+    .with_status_code(status)
+    .with_header(content_type("text/html; charset=utf-8"))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("Valid header")
+}
+
+fn form_value(form: &str, name: &str) -> String {
+    url::form_urlencoded::parse(form.as_bytes())
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_default()
+}
+
+/// Percent-decodes a path segment.
+fn decode(segment: &str) -> String {
+    url::form_urlencoded::parse(segment.replace('+', "%2B").as_bytes())
+        .next()
+        .map(|(key, _)| key.into_owned())
+        .unwrap_or_default()
+}
+
+fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['<', '>', '&', '"']) {
+        return Cow::Borrowed(text);
+    }
+
+    Cow::Owned(
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;"),
+    )
+}
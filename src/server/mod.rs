@@ -0,0 +1,93 @@
+#[cfg(feature = "server")]
+mod listener;
+
+#[cfg(feature = "server")]
+pub use listener::spawn;
+
+use crate::{clock, report::GameResult};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+#[derive(Deserialize)]
+pub struct ServerConfig {
+    /// Address to listen on in daemon mode, e.g. `0.0.0.0:9090`.
+    listen: String,
+    /// Base URL the server is reachable at from outside. Enables the cookie
+    /// refresh page linked from cookie-expired alerts.
+    pub public_url: Option<String>,
+    /// Required as `Authorization: Bearer <token>` by `/status` and `/run`.
+    /// Without it `/status` is open to anyone who can reach the server, and
+    /// `/run` is refused.
+    #[cfg_attr(not(feature = "server"), allow(dead_code))]
+    api_token: Option<String>,
+}
+
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+pub enum Trigger {
+    /// Check in every account.
+    All,
+    /// Check in the account with this name only.
+    Account(String),
+}
+
+/// Shared between the HTTP API and the daemon loop: runs requested over the
+/// API, and the results of the last run.
+#[derive(Default)]
+pub struct Control {
+    triggers: Mutex<Vec<Trigger>>,
+    last_run: Mutex<Option<Value>>,
+}
+
+impl Control {
+    #[cfg_attr(not(feature = "server"), allow(dead_code))]
+    fn trigger(&self, trigger: Trigger) {
+        self.triggers
+            .lock()
+            .expect("Triggers lock poisoned")
+            .push(trigger);
+    }
+
+    pub fn pending(&self) -> bool {
+        !self
+            .triggers
+            .lock()
+            .expect("Triggers lock poisoned")
+            .is_empty()
+    }
+
+    /// The oldest requested run, if any.
+    pub fn take(&self) -> Option<Trigger> {
+        let mut triggers = self.triggers.lock().expect("Triggers lock poisoned");
+        (!triggers.is_empty()).then(|| triggers.remove(0))
+    }
+
+    pub fn publish(&self, report: &[GameResult]) {
+        *self.last_run.lock().expect("Status lock poisoned") = Some(json!({
+            "finished_at": clock::rfc3339(clock::unix_now()),
+            "success": report.iter().all(GameResult::is_success),
+            "results": report,
+        }));
+    }
+
+    #[cfg_attr(not(feature = "server"), allow(dead_code))]
+    fn status(&self) -> Value {
+        json!({
+            "last_run": *self.last_run.lock().expect("Status lock poisoned"),
+            "pending": self.triggers.lock().expect("Triggers lock poisoned").len(),
+        })
+    }
+}
+
+#[cfg(not(feature = "server"))]
+pub fn spawn(
+    config: &ServerConfig,
+    _metrics: std::sync::Arc<crate::metrics::Metrics>,
+    _refresh: Option<std::sync::Arc<crate::refresh::CookieRefresh>>,
+    _control: std::sync::Arc<Control>,
+) {
+    tracing::warn!(
+        "Not listening on {}: this binary was built without the server feature",
+        config.listen
+    );
+}
@@ -0,0 +1,164 @@
+use crate::{
+    browser::{self, Browser},
+    client, config_file, cookies, load_config,
+    validate::CookieStatus,
+    Account, Config, HoyolabCheckin,
+};
+use reqwest::blocking::Client;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+use tracing::{info, warn};
+
+/// Asks for a new account's name and cookies, checks the cookies against
+/// HoYoLAB and appends the account to the config file, creating it if needed.
+pub fn add_account(path: &Path, profile: Option<&str>, client: &Client) -> Result<(), String> {
+    let config = create_config(path, profile)?;
+
+    let name = loop {
+        let name = prompt("Account name: ")?;
+
+        if name.is_empty() {
+            continue;
+        }
+
+        if config
+            .accounts
+            .iter()
+            .any(|account| account.name.eq_ignore_ascii_case(&name))
+        {
+            eprintln!("An account named \"{}\" already exists", name);
+            continue;
+        }
+
+        break name;
+    };
+
+    eprintln!(
+        "Log in to https://www.hoyolab.com, open the developer tools and copy the Cookie header of any request to hoyolab.com."
+    );
+
+    let cookies: BTreeMap<String, String> = loop {
+        let cookies: BTreeMap<_, _> = cookies::parse_cookie_header(&prompt("Cookies: ")?).collect();
+
+        if !cookies.is_empty() {
+            break cookies;
+        }
+    };
+
+    if let Some(reason) = check(&config, client, &name, &cookies)? {
+        warn!("Could not check the cookies: {}", reason);
+
+        if !prompt("Save anyway? [y/N] ")?.eq_ignore_ascii_case("y") {
+            return Err("Account not added".to_string());
+        }
+    }
+
+    config_file::add_account(path, profile, &name, &cookies)?;
+    info!("Added {} to {}", name, path.display());
+    Ok(())
+}
+
+/// Reads an account's cookies from a browser profile, adding the account to
+/// the config file or replacing the cookies of an existing one.
+pub fn import_cookies(
+    path: &Path,
+    profile: Option<&str>,
+    client: &Client,
+    browser: Browser,
+    account: &str,
+    database: Option<&Path>,
+) -> Result<(), String> {
+    let config = create_config(path, profile)?;
+    let cookies = browser::cookies(browser, database)?;
+
+    if let Some(reason) = check(&config, client, account, &cookies)? {
+        warn!(
+            "Could not check the cookies, saving them anyway: {}",
+            reason
+        );
+    }
+
+    match config
+        .accounts
+        .iter()
+        .find(|existing| existing.name.eq_ignore_ascii_case(account))
+    {
+        Some(existing) => {
+            config_file::save_cookies(path, profile, &existing.name, &cookies)?;
+            info!(
+                "Updated the cookies of {} in {}",
+                existing.name,
+                path.display()
+            );
+        }
+        None => {
+            config_file::add_account(path, profile, account, &cookies)?;
+            info!("Added {} to {}", account, path.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn create_config(path: &Path, profile: Option<&str>) -> Result<Config, String> {
+    if config_file::is_stdin(path) {
+        return Err("Cannot add accounts to a config read from stdin".to_string());
+    }
+
+    if !path.exists() {
+        fs::write(path, "{\n  \"accounts\": []\n}\n")
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    }
+
+    load_config(path, profile).map_err(|e| e.to_string())
+}
+
+/// Checks the cookies against HoYoLAB. Invalid cookies are an error;
+/// returns why if they could not be checked at all.
+fn check(
+    config: &Config,
+    client: &Client,
+    name: &str,
+    cookies: &BTreeMap<String, String>,
+) -> Result<Option<String>, String> {
+    let account: Account = serde_json::from_value(json!({ "name": name, "cookies": cookies }))
+        .map_err(|e| format!("Invalid cookies: {}", e))?;
+    let checkin = HoyolabCheckin::new(config, &account, client, &[], true, false);
+
+    match client::block_on(checkin.validate_cookies()).status {
+        CookieStatus::Valid { nickname } => {
+            info!(
+                "Cookies are valid{}",
+                nickname
+                    .map(|nickname| format!(" (logged in as {})", nickname))
+                    .unwrap_or_default()
+            );
+            Ok(None)
+        }
+        CookieStatus::Expired => {
+            Err("These cookies have expired, log in to HoYoLAB again".to_string())
+        }
+        CookieStatus::Malformed { reason } => {
+            Err(format!("These cookies are malformed: {}", reason))
+        }
+        CookieStatus::Unknown { reason } => Ok(Some(reason)),
+    }
+}
+
+fn prompt(question: &str) -> Result<String, String> {
+    eprint!("{}", question);
+    io::stderr().flush().ok();
+
+    let mut answer = String::new();
+
+    match io::stdin().read_line(&mut answer) {
+        Ok(0) => Err("No input".to_string()),
+        Ok(_) => Ok(answer.trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
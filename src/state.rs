@@ -0,0 +1,345 @@
+use crate::{
+    clock,
+    device::Device,
+    redeem::{RedeemStatus, Redemption},
+    report::{GameResult, RewardKind, Status},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tracing::{error, warn};
+
+/// Attempts older than this many are dropped from the history.
+const HISTORY_LIMIT: usize = 10_000;
+
+/// Data persisted between runs.
+#[derive(Serialize, Deserialize, Default)]
+pub struct State {
+    pub last_run: Option<LastRun>,
+    /// Every check-in attempt, oldest first.
+    #[serde(default)]
+    pub history: Vec<Attempt>,
+    /// Gift codes that need not be tried again, per account and game.
+    #[serde(default)]
+    pub redeemed: Vec<RedeemedCode>,
+    /// Each account's device identity, by account name.
+    #[serde(default)]
+    pub devices: BTreeMap<String, Device>,
+    /// How each account's games fared in the last notification about them,
+    /// for `notify_on_change`.
+    #[serde(default)]
+    pub notified: Vec<Notified>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LastRun {
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub success: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Attempt {
+    pub started_at: String,
+    /// HoYoLAB server day (UTC+8) the attempt counted towards.
+    pub server_date: String,
+    pub account: String,
+    pub game: String,
+    pub kind: RewardKind,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retcode: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl From<&GameResult> for Attempt {
+    fn from(result: &GameResult) -> Self {
+        Self {
+            started_at: result.started_at.clone(),
+            server_date: result.server_date.clone(),
+            account: result.account.clone(),
+            game: result.game.clone(),
+            kind: result.kind,
+            status: result.status,
+            retcode: result.retcode,
+            reward: result.reward.clone(),
+            duration_ms: result.duration_ms,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RedeemedCode {
+    pub account: String,
+    pub game: String,
+    pub code: String,
+    pub status: RedeemStatus,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Notified {
+    pub account: String,
+    pub game: String,
+    pub kind: RewardKind,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retcode: Option<i32>,
+}
+
+impl From<&GameResult> for Notified {
+    fn from(result: &GameResult) -> Self {
+        Self {
+            account: result.account.clone(),
+            game: result.game.clone(),
+            kind: result.kind,
+            status: result.status,
+            retcode: result.retcode,
+        }
+    }
+}
+
+impl Notified {
+    /// Whether the game fared the same: succeeded both times, or failed the
+    /// same way.
+    fn same(&self, other: &Self) -> bool {
+        match (succeeded(self.status), succeeded(other.status)) {
+            (true, true) => true,
+            (false, false) => self.status == other.status && self.retcode == other.retcode,
+            _ => false,
+        }
+    }
+}
+
+fn succeeded(status: Status) -> bool {
+    matches!(status, Status::Claimed | Status::AlreadyClaimed)
+}
+
+pub struct StateStore {
+    path: PathBuf,
+    pub state: State,
+    /// Where a corrupted state file was moved, for the run to notify about.
+    pub quarantined: Option<PathBuf>,
+}
+
+impl StateStore {
+    /// Loads the state file. Never fails: a missing file starts empty, and a
+    /// corrupted one is moved aside so the run can continue without state
+    /// instead of failing every day until someone intervenes.
+    pub fn open(path: &Path) -> Self {
+        Self::load(path, true)
+    }
+
+    /// Loads the state file for reading only. A corrupted one is left in
+    /// place for the next run to deal with.
+    pub fn read(path: &Path) -> Self {
+        Self::load(path, false)
+    }
+
+    fn load(path: &Path, writable: bool) -> Self {
+        let mut quarantined = None;
+        let state = match fs::read_to_string(path) {
+            Ok(data) => match serde_json::from_str(&data) {
+                Ok(state) => state,
+                Err(e) if writable => {
+                    quarantined = quarantine(path, &e.to_string());
+                    State::default()
+                }
+                Err(e) => {
+                    warn!("State file {} is corrupted: {}", path.display(), e);
+                    State::default()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => State::default(),
+            Err(e) => {
+                warn!(
+                    "Failed to read state file {}, continuing without it: {}",
+                    path.display(),
+                    e
+                );
+                State::default()
+            }
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            state,
+            quarantined,
+        }
+    }
+
+    /// Whether the game's daily reward was already claimed for the account on
+    /// the given server day.
+    pub fn claimed_on(&self, account: &str, game: &str, server_date: &str) -> bool {
+        self.state.history.iter().any(|attempt| {
+            attempt.kind == RewardKind::Daily
+                && matches!(attempt.status, Status::Claimed | Status::AlreadyClaimed)
+                && attempt.server_date == server_date
+                && attempt.account == account
+                && attempt.game == game
+        })
+    }
+
+    /// Whether the latest daily check-in attempt of the account's game failed.
+    pub fn failed_last(&self, account: &str, game: &str) -> bool {
+        self.state
+            .history
+            .iter()
+            .rev()
+            .find(|attempt| {
+                attempt.kind == RewardKind::Daily
+                    && attempt.account == account
+                    && attempt.game == game
+            })
+            .is_some_and(|attempt| {
+                matches!(
+                    attempt.status,
+                    Status::Failed | Status::CaptchaRequired | Status::CookieExpired
+                )
+            })
+    }
+
+    /// Server days the game's daily reward was claimed for the account on.
+    fn claimed_dates(&self, account: &str, game: &str) -> BTreeSet<&str> {
+        self.state
+            .history
+            .iter()
+            .filter(|attempt| {
+                attempt.kind == RewardKind::Daily
+                    && matches!(attempt.status, Status::Claimed | Status::AlreadyClaimed)
+                    && attempt.account == account
+                    && attempt.game == game
+            })
+            .map(|attempt| attempt.server_date.as_str())
+            .collect()
+    }
+
+    /// How many server days in a row before the one `now` falls on the game's
+    /// daily reward was claimed, and the last day it was claimed before that.
+    pub fn streak(&self, account: &str, game: &str, now: u64) -> (u32, Option<String>) {
+        let claimed = self.claimed_dates(account, game);
+        let today = clock::server_date(now);
+        let mut streak = 0;
+
+        while claimed.contains(clock::server_date(now - (streak + 1) * clock::DAY).as_str()) {
+            streak += 1;
+        }
+
+        let last = claimed
+            .range(..today.as_str())
+            .next_back()
+            .map(|date| date.to_string());
+
+        (streak as u32, last)
+    }
+
+    pub fn record(&mut self, report: &[GameResult]) {
+        let history = &mut self.state.history;
+
+        history.extend(report.iter().map(Attempt::from));
+        history.drain(..history.len().saturating_sub(HISTORY_LIMIT));
+    }
+
+    /// Whether the code was redeemed on the account's game, or found invalid.
+    pub fn redeemed(&self, account: &str, game: &str, code: &str) -> bool {
+        self.state.redeemed.iter().any(|redeemed| {
+            redeemed.account == account
+                && redeemed.game == game
+                && redeemed.code.eq_ignore_ascii_case(code)
+        })
+    }
+
+    /// Remembers the redemptions that settled the code either way. Failed
+    /// ones are left to be retried.
+    pub fn record_redemptions(&mut self, redemptions: &[Redemption]) {
+        self.state.redeemed.extend(
+            redemptions
+                .iter()
+                .filter(|redemption| redemption.status != RedeemStatus::Failed)
+                .map(|redemption| RedeemedCode {
+                    account: redemption.account.clone(),
+                    game: redemption.game.clone(),
+                    code: redemption.code.clone(),
+                    status: redemption.status,
+                }),
+        );
+    }
+
+    /// Compares the outcomes with the last notification about their games,
+    /// then remembers them as the latest. Returns whether any game fared
+    /// differently, and a line for each that went back to succeeding. Only
+    /// for notifications that are sent.
+    pub fn record_notified(&mut self, outcomes: Vec<Notified>) -> (bool, Vec<String>) {
+        let notified = &mut self.state.notified;
+        let mut changed = false;
+        let mut recovered = Vec::new();
+
+        for latest in outcomes {
+            match notified.iter_mut().find(|notified| {
+                notified.account == latest.account
+                    && notified.game == latest.game
+                    && notified.kind == latest.kind
+            }) {
+                Some(previous) if previous.same(&latest) => *previous = latest,
+                Some(previous) => {
+                    if latest.kind == RewardKind::Daily && succeeded(latest.status) {
+                        recovered.push(format!("✓ {}: recovered", latest.game));
+                    }
+
+                    changed = true;
+                    *previous = latest;
+                }
+                None => {
+                    changed = true;
+                    notified.push(latest);
+                }
+            }
+        }
+
+        (changed, recovered)
+    }
+
+    /// Writes the state through a temporary file so a crash mid-write cannot
+    /// leave a truncated state file behind.
+    pub fn save(&self) -> io::Result<()> {
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+
+        let data = serde_json::to_vec_pretty(&self.state)?;
+        fs::write(&temp, data)?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+/// Moves the corrupted state file aside, returning where to.
+fn quarantine(path: &Path, reason: &str) -> Option<PathBuf> {
+    let mut target = path.to_path_buf().into_os_string();
+    target.push(format!(".corrupt-{}", clock::unix_now()));
+    let target = PathBuf::from(target);
+
+    match fs::rename(path, &target) {
+        Ok(()) => {
+            error!(
+                "State file {} is corrupted ({}), moved it to {} and continuing without state",
+                path.display(),
+                reason,
+                target.display()
+            );
+            Some(target)
+        }
+        Err(e) => {
+            error!(
+                "State file {} is corrupted ({}) and could not be moved aside: {}",
+                path.display(),
+                reason,
+                e
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! `stats`: how the daily check-ins of each game fared over recent windows,
+//! from the state file's history, to tell whether a setup is degrading.
+
+use crate::{
+    clock,
+    games::Game,
+    report::{RewardKind, Status},
+    state::Attempt,
+};
+use serde::Serialize;
+use std::time::Duration;
+
+#[derive(Serialize)]
+pub struct GameStats {
+    /// The window as given, e.g. `7d`.
+    pub window: String,
+    pub game: String,
+    pub attempts: usize,
+    pub succeeded: usize,
+    pub captchas: usize,
+    /// Mean duration of the attempts that recorded one.
+    pub average_ms: Option<u64>,
+}
+
+impl GameStats {
+    fn rate(&self, count: usize) -> String {
+        match self.attempts {
+            0 => "-".to_string(),
+            attempts => format!("{:.1}%", count as f64 * 100.0 / attempts as f64),
+        }
+    }
+}
+
+/// Each game's stats over the `window` up to `now`.
+pub fn collect<'a>(
+    history: impl Iterator<Item = &'a Attempt> + Clone,
+    games: &[&dyn Game],
+    (window, duration): (&str, Duration),
+    now: u64,
+) -> Vec<GameStats> {
+    // RFC 3339 UTC timestamps sort like the times they stand for.
+    let since = clock::rfc3339(now.saturating_sub(duration.as_secs()));
+
+    games
+        .iter()
+        .map(|game| {
+            let attempts: Vec<&Attempt> = history
+                .clone()
+                .filter(|attempt| {
+                    attempt.kind == RewardKind::Daily
+                        && attempt.game == game.name()
+                        && attempt.started_at >= since
+                })
+                .collect();
+            let durations: Vec<u64> = attempts
+                .iter()
+                .filter_map(|attempt| attempt.duration_ms)
+                .collect();
+
+            GameStats {
+                window: window.to_string(),
+                game: game.name().to_string(),
+                attempts: attempts.len(),
+                succeeded: attempts
+                    .iter()
+                    .filter(|attempt| {
+                        matches!(attempt.status, Status::Claimed | Status::AlreadyClaimed)
+                    })
+                    .count(),
+                captchas: attempts
+                    .iter()
+                    .filter(|attempt| attempt.status == Status::CaptchaRequired)
+                    .count(),
+                average_ms: (!durations.is_empty())
+                    .then(|| durations.iter().sum::<u64>() / durations.len() as u64),
+            }
+        })
+        .collect()
+}
+
+/// Renders a window's stats as a table.
+pub fn table(stats: &[GameStats]) -> String {
+    let mut rows = vec![[
+        "Game".to_string(),
+        "Attempts".to_string(),
+        "Succeeded".to_string(),
+        "Captchas".to_string(),
+        "Average".to_string(),
+    ]];
+
+    rows.extend(stats.iter().map(|stats| {
+        [
+            stats.game.clone(),
+            stats.attempts.to_string(),
+            stats.rate(stats.succeeded),
+            stats.rate(stats.captchas),
+            stats
+                .average_ms
+                .map_or("-".to_string(), |ms| format!("{:.1}s", ms as f64 / 1000.0)),
+        ]
+    }));
+
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
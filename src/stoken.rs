@@ -0,0 +1,86 @@
+use crate::{client::HttpClient, CheckinError, HoyolabCheckin, SignResponse, GAMES};
+use http::{header::COOKIE, HeaderValue, Method};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::HashMap;
+
+const LTOKEN_URL: &str = "https://api-account-os.hoyoverse.com/account/auth/api/getLTokenBySToken";
+const COOKIE_TOKEN_URL: &str =
+    "https://api-account-os.hoyoverse.com/account/auth/api/getCookieAccountInfoBySToken";
+
+/// The HoYoLAB app, which v2 stokens are issued to.
+const APP_ID: &str = "c9oqaq3s3gu8";
+
+#[derive(Deserialize)]
+struct LTokenData {
+    ltoken: String,
+}
+
+#[derive(Deserialize)]
+struct CookieTokenData {
+    cookie_token: String,
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// Exchanges the account's long-lived `stoken` for fresh web session
+    /// cookies. Returns the account's cookies with the tokens replaced, or
+    /// `None` if the account has no `stoken`.
+    pub async fn refresh_cookies(&self) -> Option<Result<HashMap<String, String>, CheckinError>> {
+        let stoken = self.account.stoken.as_deref()?;
+
+        Some(self.exchange_stoken(stoken).await)
+    }
+
+    async fn exchange_stoken(&self, stoken: &str) -> Result<HashMap<String, String>, CheckinError> {
+        let ltoken: LTokenData = self.by_stoken(LTOKEN_URL, stoken).await?;
+        let cookie_token: CookieTokenData = self.by_stoken(COOKIE_TOKEN_URL, stoken).await?;
+
+        let mut cookies = self.account.cookies.clone();
+        let suffix = if stoken.starts_with("v2_") { "_v2" } else { "" };
+
+        cookies.insert(format!("ltoken{}", suffix), ltoken.ltoken);
+        cookies.insert(format!("cookie_token{}", suffix), cookie_token.cookie_token);
+
+        Ok(cookies)
+    }
+
+    async fn by_stoken<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        stoken: &str,
+    ) -> Result<T, CheckinError> {
+        let mut request = self.request(GAMES[0], Method::GET, url, &[], Vec::new())?;
+
+        // Only the stoken goes to the account API, never the web cookies.
+        let mut cookie = format!("stoken={}", stoken);
+
+        if let Some(mid) = &self.account.mid {
+            cookie.push_str(&format!("; mid={}", mid));
+        }
+
+        if let Some(uid) = ["ltuid_v2", "ltuid"]
+            .iter()
+            .find_map(|name| self.account.cookies.get(*name))
+        {
+            cookie.push_str(&format!("; stuid={}", uid));
+        }
+
+        let headers = request.headers_mut();
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_str(&cookie)
+                .map_err(|e| CheckinError::UnexpectedResponse(e.to_string()))?,
+        );
+        headers.insert("x-rpc-app_id", HeaderValue::from_static(APP_ID));
+
+        let response: SignResponse<T> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        response
+            .data
+            .ok_or_else(|| CheckinError::UnexpectedResponse("Missing data".to_string()))
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{clock, daemon::Schedule};
+use std::{
+    cell::Cell,
+    env,
+    path::Path,
+    process,
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+
+/// Speaks the `sd_notify` protocol when started by systemd with
+/// `Type=notify`, and keeps the service watchdog fed if `WatchdogSec=` is set.
+/// Does nothing when `NOTIFY_SOCKET` is absent.
+///
+/// During a pass the watchdog is only fed between accounts, so `WatchdogSec=`
+/// must exceed the longest an account can take: its requests, captcha waits
+/// and retries, plus `request_delay`.
+pub struct Notifier {
+    #[cfg(unix)]
+    socket: Option<UnixDatagram>,
+    watchdog_interval: Option<Duration>,
+    last_keepalive: Cell<Instant>,
+}
+
+impl Notifier {
+    pub fn from_env() -> Self {
+        Self {
+            #[cfg(unix)]
+            socket: env::var("NOTIFY_SOCKET")
+                .ok()
+                .and_then(|path| connect(&path)),
+            watchdog_interval: watchdog_interval(),
+            last_keepalive: Cell::new(Instant::now()),
+        }
+    }
+
+    pub fn notify(&self, state: &str) {
+        #[cfg(unix)]
+        if let Some(socket) = &self.socket {
+            let _ = socket.send(state.as_bytes());
+        }
+
+        #[cfg(not(unix))]
+        let _ = state;
+    }
+
+    pub fn status(&self, status: &str) {
+        self.notify(&format!("STATUS={}", status));
+    }
+
+    /// Sends `WATCHDOG=1` if half of the watchdog timeout has elapsed since
+    /// the previous keepalive, as recommended by `sd_watchdog_enabled(3)`.
+    pub fn keepalive(&self) {
+        if let Some(interval) = self.watchdog_interval {
+            if self.last_keepalive.get().elapsed() >= interval {
+                self.notify("WATCHDOG=1");
+                self.last_keepalive.set(Instant::now());
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect(path: &str) -> Option<UnixDatagram> {
+    let socket = UnixDatagram::unbound().ok()?;
+
+    match path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            use std::os::unix::net::SocketAddr;
+
+            let address = SocketAddr::from_abstract_name(name).ok()?;
+            socket.connect_addr(&address).ok()?;
+        }
+        _ => socket.connect(path).ok()?,
+    }
+
+    Some(socket)
+}
+
+fn watchdog_interval() -> Option<Duration> {
+    if let Ok(pid) = env::var("WATCHDOG_PID") {
+        if pid.parse() != Ok(process::id()) {
+            return None;
+        }
+    }
+
+    let usec: u64 = env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(usec / 2))
+}
+
+/// The unit files `generate systemd` writes.
+pub const SERVICE: &str = "hoyo-checkin.service";
+pub const TIMER: &str = "hoyo-checkin.timer";
+
+/// A oneshot service running `exec` and a timer starting it on `schedule`,
+/// right after the server reset without one.
+pub fn units(
+    exec: &[&str],
+    working_directory: &Path,
+    user: Option<&str>,
+    schedule: Option<&Schedule>,
+) -> (String, String) {
+    let mut service = String::from(
+        "[Unit]\n\
+         Description=HoYoLAB daily check-in\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n",
+    );
+
+    if let Some(user) = user {
+        service.push_str(&format!("User={}\n", user));
+    }
+
+    service.push_str(&format!(
+        "WorkingDirectory={}\nExecStart={}\n",
+        working_directory.display().to_string().replace('%', "%%"),
+        exec.iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+
+    let trigger = match schedule {
+        Some(Schedule::Interval(interval)) => {
+            format!("OnBootSec=1min\nOnUnitActiveSec={}s", interval.as_secs())
+        }
+        Some(Schedule::AfterReset(offset)) => on_calendar(*offset),
+        None => on_calendar(Duration::ZERO),
+    };
+    let timer = format!(
+        "[Unit]\n\
+         Description=Run the HoYoLAB daily check-in\n\
+         \n\
+         [Timer]\n\
+         {}\n\
+         Persistent=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        trigger
+    );
+
+    (service, timer)
+}
+
+/// Daily at `offset` past the server reset, midnight UTC+8.
+fn on_calendar(offset: Duration) -> String {
+    let time = (clock::DAY - clock::SERVER_UTC_OFFSET + offset.as_secs()) % clock::DAY;
+
+    format!(
+        "OnCalendar=*-*-* {:02}:{:02}:{:02} UTC",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Quotes an `ExecStart=` word that would otherwise be split or expanded.
+fn quote(word: &str) -> String {
+    if word
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '$' | '%'))
+    {
+        format!(
+            "\"{}\"",
+            word.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('$', "$$")
+                .replace('%', "%%")
+        )
+    } else {
+        word.to_string()
+    }
+}
@@ -0,0 +1,60 @@
+// Without the otlp feature the config is parsed but never used.
+#![cfg_attr(not(feature = "otlp"), allow(dead_code))]
+
+use serde::Deserialize;
+
+/// Target of spans that only exist for traces and would clutter the logs.
+pub const TARGET: &str = "hoyo_checkin_rs::trace";
+
+/// Exports every run as an OpenTelemetry trace over OTLP/HTTP, with spans per
+/// account, game and request.
+#[derive(Deserialize)]
+pub struct OtlpConfig {
+    /// Traces endpoint of the collector, e.g. `http://localhost:4318/v1/traces`.
+    /// Falls back to the standard `OTEL_EXPORTER_OTLP_*` variables.
+    endpoint: Option<String>,
+    /// Defaults to the package name.
+    service_name: Option<String>,
+}
+
+#[cfg(feature = "otlp")]
+pub fn layer<S>(config: &OtlpConfig) -> Result<impl tracing_subscriber::Layer<S>, String>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+
+    let mut exporter = SpanExporter::builder().with_http();
+
+    if let Some(endpoint) = &config.endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+
+    let exporter = exporter
+        .build()
+        .map_err(|e| format!("Failed to set up OTLP export: {}", e))?;
+    // Spans are exported as they close, so nothing is lost when a one-shot
+    // run exits without shutting the provider down.
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(
+                    config
+                        .service_name
+                        .clone()
+                        .unwrap_or_else(|| env!("CARGO_PKG_NAME").to_string()),
+                )
+                .build(),
+        )
+        .build();
+
+    Ok(tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME"))))
+}
+
+#[cfg(not(feature = "otlp"))]
+pub fn layer(_config: &OtlpConfig) -> Result<tracing_subscriber::layer::Identity, String> {
+    Err("this binary was built without the otlp feature".to_string())
+}
@@ -0,0 +1,1219 @@
+//! Check-in runs against scripted HoYoLAB responses.
+
+use crate::{
+    breaker::CircuitBreaker,
+    cli::Delay,
+    client::{self, mock::MockClient},
+    clock,
+    games::{Game, Genshin, GAMES},
+    notify::Notification,
+    report::{GameResult, RewardKind, Status},
+    state::{Attempt, Notified, StateStore},
+    CheckinError, Config, HoyolabCheckin, Runner,
+};
+use serde_json::json;
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+const INFO: &str = "/event/sol/info";
+const SIGN: &str = "/event/sol/sign";
+const HOME: &str = "/event/sol/home";
+
+const UNSIGNED: &str =
+    r#"{"retcode":0,"message":"OK","data":{"is_sign":false,"total_sign_day":2}}"#;
+const SIGNED: &str = r#"{"retcode":0,"message":"OK","data":{"is_sign":true,"total_sign_day":3}}"#;
+const SIGN_OK: &str = r#"{"retcode":0,"message":"OK","data":{"code":"ok","risk_code":0,"gt":"","challenge":"","success":0,"is_risk":false}}"#;
+const HOME_DATA: &str = r#"{"retcode":0,"message":"OK","data":{"month":10,"awards":[{"name":"Primogem","cnt":20},{"name":"Mora","cnt":5000},{"name":"Hero's Wit","cnt":2}]}}"#;
+
+fn config(extra: serde_json::Value) -> Config {
+    let mut config = json!({
+        "accounts": [{
+            "name": "alice",
+            "cookies": { "ltoken_v2": "token", "ltuid_v2": "1" },
+        }],
+    });
+
+    if let (Some(config), Some(extra)) = (config.as_object_mut(), extra.as_object()) {
+        config.extend(extra.clone());
+    }
+
+    serde_json::from_value(config).expect("Valid test config")
+}
+
+fn run(config: &Config, client: &MockClient) -> Vec<GameResult> {
+    let games: &[&dyn Game] = &[&Genshin];
+    let checkin = HoyolabCheckin::new(config, &config.accounts[0], client, games, false, false);
+
+    client::block_on(checkin.process())
+}
+
+/// Answers every HTTP request on a local port with 200, keeping the bodies,
+/// to stand in for a notifier.
+fn listen() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Free local port");
+    let url = format!("http://{}/", listener.local_addr().expect("Bound"));
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&bodies);
+
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut length = 0;
+            let mut line = String::new();
+
+            while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                line.clear();
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).ok();
+            received
+                .lock()
+                .expect("Listener lock poisoned")
+                .push(String::from_utf8_lossy(&body).into_owned());
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .ok();
+        }
+    });
+
+    (url, bodies)
+}
+
+/// Loads a config file holding only alice's account, with the given fields,
+/// the way `load_config` reads it from disk. `name` keeps the file apart from
+/// those of other tests.
+fn load_account(name: &str, fields: serde_json::Value) -> Result<Config, crate::ConfigError> {
+    let path = std::env::temp_dir().join(format!("hoyo-{}-{}.json", name, std::process::id()));
+    let mut account = json!({ "name": "alice" });
+
+    if let (Some(account), Some(fields)) = (account.as_object_mut(), fields.as_object()) {
+        account.extend(fields.clone());
+    }
+
+    std::fs::write(&path, json!({ "accounts": [account] }).to_string()).expect("Writable temp dir");
+    let config = crate::load_config(&path, None);
+    std::fs::remove_file(&path).ok();
+
+    config
+}
+
+/// Runs `f` with the variables set, one test at a time, as the environment
+/// is shared by the whole test binary. They are removed afterwards.
+fn with_env<T>(variables: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+    static ENV: Mutex<()> = Mutex::new(());
+    let _lock = ENV.lock().unwrap_or_else(PoisonError::into_inner);
+
+    for (name, value) in variables {
+        std::env::set_var(name, value);
+    }
+    let result = f();
+    for (name, _) in variables {
+        std::env::remove_var(name);
+    }
+
+    result
+}
+
+fn single(config: &Config, client: &MockClient) -> GameResult {
+    let mut results = run(config, client);
+    assert_eq!(results.len(), 1, "Genshin has no event bonus");
+    results.remove(0)
+}
+
+#[test]
+fn signs_and_reports_the_reward() {
+    let client = MockClient::default()
+        .on(INFO, 200, UNSIGNED)
+        .on(INFO, 200, SIGNED)
+        .on(SIGN, 200, SIGN_OK)
+        .on(HOME, 200, HOME_DATA);
+
+    let result = single(&config(json!({})), &client);
+
+    assert!(result.status == Status::Claimed);
+    assert!(result.error.is_none());
+    assert_eq!(result.reward.as_deref(), Some("Hero's Wit ×2"));
+    assert_eq!(client.requests(SIGN).len(), 1);
+}
+
+#[test]
+fn skips_signing_when_already_claimed() {
+    let client = MockClient::default().on(INFO, 200, SIGNED);
+
+    let result = single(&config(json!({})), &client);
+
+    assert!(result.status == Status::AlreadyClaimed);
+    assert!(client.requests(SIGN).is_empty());
+}
+
+#[test]
+fn treats_already_signed_retcode_as_success() {
+    let client = MockClient::default()
+        .on(INFO, 200, UNSIGNED)
+        .on(INFO, 200, SIGNED)
+        .on(
+            SIGN,
+            200,
+            r#"{"retcode":-5003,"message":"Traveler, you've already checked in today~","data":null}"#,
+        )
+        .on(HOME, 200, HOME_DATA);
+
+    let result = single(&config(json!({})), &client);
+
+    assert!(result.is_success());
+    assert!(result.status == Status::Claimed);
+}
+
+#[test]
+fn reports_expired_cookies() {
+    let client = MockClient::default().on(
+        INFO,
+        200,
+        r#"{"retcode":-100,"message":"Please log in","data":null}"#,
+    );
+
+    let result = single(&config(json!({})), &client);
+
+    assert!(result.status == Status::CookieExpired);
+    assert_eq!(result.retcode, Some(-100));
+    assert!(matches!(
+        result.error,
+        Some(CheckinError::CookieExpired { retcode: -100 })
+    ));
+}
+
+#[test]
+fn reports_captcha_challenge() {
+    let client = MockClient::default()
+        .on(INFO, 200, UNSIGNED)
+        .on(
+            SIGN,
+            200,
+            r#"{"retcode":0,"message":"OK","data":{"code":"ok","risk_code":375,"gt":"gt-id","challenge":"challenge-id","success":1,"is_risk":true}}"#,
+        );
+
+    let result = single(&config(json!({})), &client);
+
+    assert!(result.status == Status::CaptchaRequired);
+    let captcha = result.captcha.expect("Challenge is reported");
+    assert_eq!(captcha.gt.as_deref(), Some("gt-id"));
+    assert_eq!(captcha.challenge.as_deref(), Some("challenge-id"));
+}
+
+#[test]
+fn retries_captcha_with_configured_token() {
+    let client = MockClient::default()
+        .on(INFO, 200, UNSIGNED)
+        .on(INFO, 200, SIGNED)
+        .on(
+            SIGN,
+            200,
+            r#"{"retcode":0,"message":"OK","data":{"risk_code":375,"gt":"gt-id","challenge":"challenge-id","is_risk":true}}"#,
+        )
+        .on(SIGN, 200, SIGN_OK)
+        .on(HOME, 200, HOME_DATA);
+    let mut config = config(json!({}));
+    config.accounts[0].captcha =
+        serde_json::from_value(json!({ "challenge": "challenge-id", "validate": "solved" }))
+            .expect("Valid token");
+
+    let result = single(&config, &client);
+
+    assert!(result.status == Status::Claimed);
+    let retry = &client.requests(SIGN)[1];
+    assert_eq!(retry.headers()["x-rpc-validate"], "solved");
+    assert_eq!(retry.headers()["x-rpc-seccode"], "solved|jordan");
+}
+
+#[test]
+fn flags_malformed_json_as_api_change() {
+    let client = MockClient::default().on(INFO, 200, "<html>Service Unavailable</html>");
+
+    let result = single(&config(json!({})), &client);
+
+    assert!(result.status == Status::Failed);
+    assert!(result
+        .error
+        .as_ref()
+        .is_some_and(CheckinError::is_api_change));
+}
+
+#[test]
+fn treats_rate_limits_and_outages_as_network_errors() {
+    for client in [
+        MockClient::default().on(INFO, 429, ""),
+        MockClient::default().on(INFO, 502, ""),
+        MockClient::default().fail(INFO, "connection reset"),
+    ] {
+        let result = single(&config(json!({})), &client);
+
+        assert!(result.status == Status::Failed);
+        assert!(matches!(result.error, Some(CheckinError::Network(_))));
+    }
+}
+
+#[test]
+fn fails_when_sign_does_not_stick() {
+    let client = MockClient::default()
+        .on(INFO, 200, UNSIGNED)
+        .on(SIGN, 200, SIGN_OK);
+
+    let result = single(&config(json!({})), &client);
+
+    assert!(result.status == Status::Failed);
+    assert!(matches!(result.error, Some(CheckinError::Unclaimed)));
+}
+
+#[test]
+fn sends_cookies_and_language() {
+    let client = MockClient::default().on(INFO, 200, SIGNED);
+
+    single(&config(json!({ "lang": "de-de" })), &client);
+
+    let request = &client.requests(INFO)[0];
+    let cookie = request.headers()["Cookie"].to_str().expect("ASCII cookies");
+    assert!(cookie.contains("ltoken_v2=token"));
+    assert!(cookie.contains("ltuid_v2=1"));
+    assert!(request
+        .uri()
+        .query()
+        .is_some_and(|query| query.contains("lang=de-de")));
+}
+
+#[test]
+fn dry_run_never_signs() {
+    let client = MockClient::default().on(INFO, 200, UNSIGNED);
+    let config = config(json!({}));
+    let games: &[&dyn Game] = &[&Genshin];
+    let checkin = HoyolabCheckin::new(&config, &config.accounts[0], &client, games, true, false);
+
+    let results = client::block_on(checkin.process());
+
+    assert!(results[0].status == Status::WouldClaim);
+    assert!(client.requests(SIGN).is_empty());
+}
+
+#[test]
+fn explains_known_return_codes() {
+    let client = MockClient::default().on(INFO, 200, UNSIGNED).on(
+        SIGN,
+        200,
+        r#"{"retcode":-5001,"message":"活动未开始","data":null}"#,
+    );
+
+    let result = single(&config(json!({})), &client);
+
+    assert_eq!(result.retcode, Some(-5001));
+    assert_eq!(
+        result.error.map(|e| e.to_string()).as_deref(),
+        Some("The check-in event is not running (-5001)")
+    );
+}
+
+#[test]
+fn picks_request_delays_within_range() {
+    let delay = Delay::parse("1s-3s").expect("Valid range");
+
+    for _ in 0..20 {
+        let picked = delay.pick();
+        assert!((Duration::from_secs(1)..=Duration::from_secs(3)).contains(&picked));
+    }
+
+    assert_eq!(
+        Delay::parse("2s").expect("Valid delay").pick(),
+        Duration::from_secs(2)
+    );
+    assert!(Delay::parse("3s-1s").is_err());
+}
+
+#[test]
+fn accepts_zero_delays_and_rejects_overflowing_durations() {
+    let delay = Delay::parse("0s-5s").expect("Zero may start a range");
+    assert!(delay.pick() <= Duration::from_secs(5));
+    assert_eq!(
+        Delay::parse("0s").expect("Valid delay").pick(),
+        Duration::ZERO
+    );
+
+    assert!(crate::cli::parse_duration("99999999999999999999d").is_err());
+    assert!(crate::cli::parse_duration("18446744073709551615s1s").is_err());
+    assert!(crate::cli::parse_duration("").is_err());
+    assert!(crate::daemon::Schedule::parse("0s").is_err());
+}
+
+#[test]
+fn generates_devices_and_rejects_malformed_ids() {
+    let device = crate::device::Device::generate().expect("Random device");
+    assert!(device.is_valid());
+    assert_eq!(device.fp.len(), 13);
+
+    for id in ["", "abc-123", "zzzzzzzz-zzzz-4zzz-azzz-zzzzzzzzzzzz"] {
+        let device = crate::device::Device {
+            id: id.to_string(),
+            fp: device.fp.clone(),
+        };
+        assert!(!device.is_valid(), "{:?} is not a device id", id);
+    }
+}
+
+#[test]
+fn selects_accounts_by_name_tag_and_enabled_flag() {
+    let config: Config = serde_json::from_value(json!({
+        "accounts": [
+            { "name": "alice", "cookies": {}, "tags": ["Priority"] },
+            { "name": "bob", "cookies": {}, "enabled": false, "tags": ["priority"] },
+        ],
+    }))
+    .expect("Valid test config");
+    let (alice, bob) = (&config.accounts[0], &config.accounts[1]);
+    let names = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert!(alice.selected(&[], &[]));
+    assert!(!bob.selected(&[], &[]));
+    assert!(bob.selected(&names(&["Bob"]), &[]));
+    assert!(!alice.selected(&names(&["bob"]), &[]));
+
+    assert!(alice.selected(&[], &names(&["priority"])));
+    assert!(!alice.selected(&[], &names(&["alt"])));
+    assert!(!bob.selected(&[], &names(&["priority"])));
+    assert!(bob.selected(&names(&["bob"]), &names(&["priority"])));
+}
+
+#[test]
+fn applies_game_overrides() {
+    let client = MockClient::default()
+        .on("/rotated/info", 200, SIGNED)
+        .on(INFO, 200, UNSIGNED);
+    let config = config(json!({
+        "games": {
+            "genshin": { "act_id": "e2026", "url_get_status": "https://example.com/rotated/info" },
+        },
+    }));
+    let games = [crate::games::configure(
+        &Genshin,
+        config.games.get("genshin"),
+    )];
+    let checkin = HoyolabCheckin::new(&config, &config.accounts[0], &client, &games, false, false);
+
+    let results = client::block_on(checkin.process());
+
+    assert!(results[0].status == Status::AlreadyClaimed);
+    let request = &client.requests("/rotated/info")[0];
+    assert!(request
+        .uri()
+        .query()
+        .is_some_and(|query| query.contains("act_id=e2026")));
+}
+
+#[test]
+fn signs_webhooks_like_github() {
+    // The example from GitHub's webhook validation docs.
+    assert_eq!(
+        crate::notify::signature("It's a Secret to Everybody", b"Hello, World!"),
+        "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+    );
+}
+
+#[test]
+fn makes_up_missed_days_within_limits_and_coins() {
+    const RESIGN: &str = "/event/sol/resign";
+
+    let client = MockClient::default()
+        .on(
+            "/event/sol/resign_info",
+            200,
+            r#"{"retcode":0,"message":"OK","data":{"sign_cnt_missed":4,"resign_cnt_daily":0,"resign_limit_daily":3,"resign_cnt_monthly":1,"resign_limit_monthly":3,"coin_cnt":250,"coin_cost":100}}"#,
+        )
+        .on(RESIGN, 200, r#"{"retcode":0,"message":"OK","data":null}"#);
+    let config = config(json!({}));
+    let games: &[&dyn Game] = &[&Genshin];
+    let checkin = HoyolabCheckin::new(&config, &config.accounts[0], &client, games, false, false);
+
+    let days = client::block_on(checkin.makeup_sign(&Genshin)).expect("Days made up");
+
+    assert_eq!(days, 2);
+    assert_eq!(client.requests(RESIGN).len(), 2);
+}
+
+#[test]
+fn counts_streaks_from_history() {
+    let now = 1_790_000_000;
+    let mut store = StateStore::open(std::path::Path::new("/nonexistent/state.json"));
+    let claim = |days_ago: u64| Attempt {
+        started_at: String::new(),
+        server_date: clock::server_date(now - days_ago * clock::DAY),
+        account: "alice".to_string(),
+        game: Genshin.name().to_string(),
+        kind: RewardKind::Daily,
+        status: Status::Claimed,
+        retcode: None,
+        reward: None,
+        duration_ms: None,
+    };
+    store.state.history = vec![claim(5), claim(2), claim(1)];
+
+    assert_eq!(store.streak("alice", Genshin.name(), now).0, 2);
+
+    let (streak, last) = store.streak("alice", Genshin.name(), now + clock::DAY);
+    assert_eq!(streak, 0);
+    assert_eq!(last, Some(clock::server_date(now - clock::DAY)));
+    assert_eq!(store.streak("bob", Genshin.name(), now), (0, None));
+}
+
+#[test]
+fn quarantines_a_corrupted_state_file_only_when_writing() {
+    let path = std::env::temp_dir().join(format!("hoyo-corrupt-{}.json", std::process::id()));
+    std::fs::write(&path, "{").expect("Writable temp dir");
+
+    let read = StateStore::read(&path);
+    let kept = path.exists();
+    let opened = StateStore::open(&path);
+    let moved = opened.quarantined.clone().expect("Quarantined");
+    std::fs::remove_file(&moved).ok();
+
+    assert!(read.quarantined.is_none());
+    assert!(kept, "Read-only commands leave the file in place");
+    assert!(!path.exists());
+    assert!(moved.to_string_lossy().contains(".corrupt-"));
+}
+
+#[test]
+fn notifies_only_when_a_game_fares_differently() {
+    let mut store = StateStore::open(std::path::Path::new("/nonexistent/state.json"));
+    let expired = || {
+        vec![Notified::from(&GameResult::new(
+            "alice",
+            &Genshin,
+            0,
+            Err(CheckinError::CookieExpired { retcode: -100 }),
+        ))]
+    };
+    let claimed = |status| {
+        vec![Notified::from(&GameResult::new(
+            "alice",
+            &Genshin,
+            0,
+            Ok(status),
+        ))]
+    };
+
+    assert_eq!(store.record_notified(expired()), (true, Vec::new()));
+    assert_eq!(store.record_notified(expired()), (false, Vec::new()));
+    assert_eq!(
+        store.record_notified(claimed(Status::Claimed)),
+        (true, vec![format!("✓ {}: recovered", Genshin.name())])
+    );
+    assert_eq!(
+        store.record_notified(claimed(Status::AlreadyClaimed)),
+        (false, Vec::new())
+    );
+}
+
+/// A daemon's runner for alice, whose check-ins fail to connect.
+fn failing_runner(
+    client: &reqwest::blocking::Client,
+    extra: serde_json::Value,
+    grace_window: Option<Duration>,
+) -> Runner<'_> {
+    let mut config = config(json!({ "base_url": "http://127.0.0.1:1" }));
+    let overrides: Config = serde_json::from_value(extra).expect("Valid config");
+    config.notify_on_change = overrides.notify_on_change;
+    config.notifiers = overrides.notifiers;
+    config.state_file = overrides.state_file;
+    let metrics = Arc::new(crate::metrics::Metrics::new());
+
+    Runner {
+        state: config.state_file.as_deref().map(StateStore::open),
+        config,
+        config_path: "config.json".into(),
+        profile: None,
+        client,
+        account_filter: Vec::new(),
+        tag_filter: Vec::new(),
+        games: vec![&Genshin],
+        output: crate::cli::OutputFormat::Text,
+        summary: false,
+        color: false,
+        progress: false,
+        output_file: None,
+        gha: false,
+        dry_run: false,
+        interactive: false,
+        force: false,
+        retry_failed: false,
+        grace_window,
+        retry_follows: false,
+        metrics: Arc::clone(&metrics),
+        api: crate::metrics::TimedClient::new(client::Backend::Reqwest(client), metrics),
+        refresh: None,
+        shutdown: None,
+        watchdog: None,
+    }
+}
+
+#[test]
+fn alerts_on_change_once_retries_run_out() {
+    let (url, received) = listen();
+    let state = std::env::temp_dir().join(format!("hoyo-grace-{}.json", std::process::id()));
+    std::fs::remove_file(&state).ok();
+    let client = reqwest::blocking::Client::new();
+    let mut runner = failing_runner(
+        &client,
+        json!({
+            "accounts": [],
+            "notify_on_change": true,
+            "notifiers": [{ "type": "webhook", "url": url }],
+            "state_file": state,
+        }),
+        Some(Duration::ZERO),
+    );
+
+    runner.retry_follows = true;
+    runner.run();
+    let held_back = received.lock().expect("Listener lock poisoned").len();
+    runner.retry_follows = false;
+    runner.retry();
+    std::fs::remove_file(&state).ok();
+
+    assert_eq!(
+        held_back, 0,
+        "Failures within the grace window wait for a retry"
+    );
+    let received = received.lock().expect("Listener lock poisoned");
+    assert_eq!(received.len(), 1);
+    assert!(received[0].contains("Check-in failed for alice"));
+}
+
+#[test]
+fn only_the_last_retry_alerts_without_a_grace_window() {
+    let (url, received) = listen();
+    let client = reqwest::blocking::Client::new();
+    let mut runner = failing_runner(
+        &client,
+        json!({
+            "accounts": [],
+            "notifiers": [{ "type": "webhook", "url": url }],
+        }),
+        None,
+    );
+
+    runner.retry_follows = true;
+    runner.run();
+    runner.retry();
+    runner.retry_follows = false;
+    runner.retry();
+
+    let received = received.lock().expect("Listener lock poisoned");
+    assert_eq!(received.len(), 1, "Only the last retry alerts");
+    assert!(received[0].contains("Check-in failed for alice"));
+}
+
+#[test]
+fn reports_the_month_and_streak_on_success() {
+    let client = MockClient::default()
+        .on(INFO, 200, UNSIGNED)
+        .on(INFO, 200, SIGNED)
+        .on(SIGN, 200, SIGN_OK)
+        .on(HOME, 200, HOME_DATA);
+    let mut result = single(&config(json!({})), &client);
+    result.streak = Some(14);
+
+    assert_eq!(result.sign_day, Some(3));
+    assert_eq!(
+        Notification::for_account("alice", &[result]).message,
+        "✓ Genshin Impact: claimed Hero's Wit ×2 (day 3 of this month, 14-day streak)"
+    );
+}
+
+#[test]
+fn combines_a_run_into_one_notification() {
+    let alice = Notification::for_account(
+        "alice",
+        &[GameResult::new(
+            "alice",
+            &Genshin,
+            0,
+            Ok(Status::AlreadyClaimed),
+        )],
+    );
+    let bob = Notification::for_account(
+        "bob",
+        &[GameResult::new(
+            "bob",
+            &Genshin,
+            0,
+            Err(CheckinError::Unclaimed),
+        )],
+    );
+
+    let combined = Notification::combine(vec![alice.clone(), bob]).expect("Notification");
+
+    assert!(!combined.success);
+    assert_eq!(combined.title, "Check-in failed for 1 of 2 accounts");
+    assert!(combined
+        .message
+        .starts_with("alice\n✓ Genshin Impact: already claimed\n\nbob\n✗"));
+    assert_eq!(
+        Notification::combine(vec![alice]).map(|notification| notification.title),
+        Some("Check-in succeeded for alice".to_string())
+    );
+    assert!(Notification::combine(Vec::new()).is_none());
+}
+
+#[test]
+fn splits_oversized_notifications_between_accounts() {
+    let notifications = (0..100)
+        .map(|account| {
+            Notification::for_account(
+                &format!("account{}", account),
+                &GAMES
+                    .iter()
+                    .map(|game| GameResult::new("", *game, 0, Err(CheckinError::Unclaimed)))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    let combined = Notification::combine(notifications).expect("Notification");
+    let texts = combined.split("**Check-in**", 2000);
+
+    assert!(texts.len() > 1);
+    assert!(texts
+        .iter()
+        .all(|text| text.chars().count() <= 2000 && text.starts_with("**Check-in**\naccount")));
+    assert_eq!(
+        texts.concat().matches("\naccount").count(),
+        100,
+        "Every account is sent once, whole"
+    );
+
+    let mut long = Notification::for_account("alice", &[]);
+    long.message = format!(
+        "alice\n{}",
+        vec!["✗ Genshin Impact: failed"; 200].join("\n")
+    );
+    let texts = long.split("Check-in", 500);
+
+    assert_eq!(texts.len(), 1);
+    assert!(texts[0].chars().count() <= 500);
+    assert!(texts[0].ends_with(" more"), "{}", texts[0]);
+}
+
+#[test]
+fn claims_the_event_bonus_alongside_the_daily_reward() {
+    const EXTRA: &str = "/event/luna/os/extra_award";
+
+    let client = MockClient::default()
+        .on("/event/luna/os/info", 200, SIGNED)
+        .on(
+            EXTRA,
+            200,
+            r#"{"retcode":0,"message":"OK","data":{"has_short_act":true,"short_sign_day":2,"total_cnt":7}}"#,
+        );
+    let config = config(json!({}));
+    let games: Vec<&dyn Game> = GAMES
+        .iter()
+        .copied()
+        .filter(|game| game.id() == "hsr")
+        .collect();
+    let checkin = HoyolabCheckin::new(&config, &config.accounts[0], &client, &games, false, false);
+
+    let results = client::block_on(checkin.process());
+
+    assert_eq!(results.len(), 2);
+    assert!(results[0].kind == RewardKind::Daily && results[0].status == Status::AlreadyClaimed);
+    assert!(results[1].kind == RewardKind::Extra && results[1].status == Status::Claimed);
+    let requests = client.requests(EXTRA);
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].method(), http::Method::POST);
+}
+
+#[test]
+fn merges_configured_headers() {
+    let client = MockClient::default().on(INFO, 200, SIGNED);
+    let config = config(json!({
+        "accounts": [{
+            "name": "alice",
+            "cookies": {},
+            "headers": { "X-Rpc-New": "alice" },
+        }],
+        "headers": { "x-rpc-new": "global", "x-rpc-app_version": "", "x-rpc-client_type": "5" },
+    }));
+
+    single(&config, &client);
+
+    let headers = client.requests(INFO)[0].headers().clone();
+    assert_eq!(headers["x-rpc-new"], "alice");
+    assert_eq!(headers["x-rpc-client_type"], "5");
+    assert!(!headers.contains_key("x-rpc-app_version"));
+}
+
+#[test]
+fn rejects_invalid_header_names() {
+    let config = json!({ "accounts": [], "headers": { "bad name": "value" } });
+
+    assert!(serde_json::from_value::<Config>(config).is_err());
+}
+
+#[test]
+fn waits_out_rate_limits_within_the_cap() {
+    let rate_limited = |seconds: &str| {
+        http::Response::builder()
+            .status(429)
+            .header("Retry-After", seconds)
+            .body(Vec::new())
+            .expect("Valid response")
+    };
+
+    let client = MockClient::default()
+        .respond(INFO, rate_limited("20"))
+        .on(INFO, 200, SIGNED);
+    let result = single(&config(json!({ "max_retry_after": "30s" })), &client);
+
+    assert!(result.status == Status::AlreadyClaimed);
+    assert_eq!(client.slept(), [Duration::from_secs(20)]);
+
+    let client = MockClient::default()
+        .respond(INFO, rate_limited("20"))
+        .respond(INFO, rate_limited("20"))
+        .on(INFO, 200, SIGNED);
+    let result = single(&config(json!({ "max_retry_after": "30s" })), &client);
+
+    assert!(matches!(result.error, Some(CheckinError::Network(_))));
+    assert_eq!(client.requests(INFO).len(), 2);
+}
+
+#[test]
+fn reads_hoyolab_cookies_from_cookies_txt() {
+    let path = std::env::temp_dir().join(format!("hoyo-cookies-{}.txt", std::process::id()));
+    std::fs::write(
+        &path,
+        "# Netscape HTTP Cookie File\n\
+         .hoyolab.com\tTRUE\t/\tTRUE\t0\tltuid_v2\t1\n\
+         #HttpOnly_.hoyolab.com\tTRUE\t/\tTRUE\t0\tltoken_v2\ttoken\n\
+         .hoyolab.com\tTRUE\t/\tTRUE\t1\tstale\tgone\n\
+         .example.com\tTRUE\t/\tFALSE\t0\tltoken_v2\tforeign\n",
+    )
+    .expect("Writable temp dir");
+
+    let cookies = crate::cookies::from_cookies_txt(&path);
+    std::fs::remove_file(&path).ok();
+
+    let cookies = cookies.expect("HoYoLAB cookies");
+    assert_eq!(cookies.len(), 2);
+    assert_eq!(cookies["ltuid_v2"], "1");
+    assert_eq!(cookies["ltoken_v2"], "token");
+}
+
+#[test]
+fn reads_cookies_from_the_environment() {
+    let variable = format!("HOYO_TEST_COOKIES_{}", std::process::id());
+
+    let (config, missing) = with_env(&[(&variable, "ltuid_v2=1; ltoken_v2=secret")], || {
+        (
+            load_account(
+                "env",
+                json!({ "cookies": { "ltuid_v2": "inline" }, "cookies_env": variable }),
+            ),
+            load_account(
+                "env",
+                json!({
+                    "cookies": { "ltuid_v2": "inline" },
+                    "cookies_env": "HOYO_TEST_COOKIES_UNSET",
+                }),
+            ),
+        )
+    });
+
+    let cookies = &config.expect("Valid config").accounts[0].cookies;
+    assert_eq!(cookies["ltuid_v2"], "inline");
+    assert_eq!(cookies["ltoken_v2"], "secret");
+    assert!(matches!(missing, Err(crate::ConfigError::Cookies { .. })));
+}
+
+#[cfg(unix)]
+#[test]
+fn reads_cookies_from_a_command() {
+    let config = load_account(
+        "command",
+        json!({
+            "cookies": { "ltuid_v2": "inline" },
+            "cookies_cmd": "echo 'ltuid_v2=1; ltoken_v2=secret'",
+        }),
+    );
+    let failed = load_account(
+        "command",
+        json!({ "cookies": { "ltuid_v2": "inline" }, "cookies_cmd": "exit 1" }),
+    );
+
+    let cookies = &config.expect("Valid config").accounts[0].cookies;
+    assert_eq!(cookies["ltuid_v2"], "inline");
+    assert_eq!(cookies["ltoken_v2"], "secret");
+    assert!(matches!(failed, Err(crate::ConfigError::Cookies { .. })));
+}
+
+#[test]
+fn trims_secrets_and_refuses_unsendable_cookies() {
+    let config = load_account(
+        "trim",
+        json!({ "cookies": { "ltuid_v2": "1", "ltoken_v2": "secret\n" } }),
+    );
+    let unsendable = load_account(
+        "trim",
+        json!({ "cookies": { "ltuid_v2": "1", "ltoken_v2": "sec\u{7}ret" } }),
+    );
+
+    assert_eq!(
+        config.expect("Valid config").accounts[0].cookies["ltoken_v2"],
+        "secret"
+    );
+    assert!(matches!(
+        unsendable,
+        Err(crate::ConfigError::Cookies { error, .. }) if error.contains("ltoken_v2")
+    ));
+}
+
+#[test]
+fn locates_config_mistakes() {
+    let path = std::env::temp_dir().join(format!("hoyo-schema-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        json!({
+            "accounts": [
+                { "name": "alice", "cookies": { "ltuid_v2": "1" } },
+                { "name": "bob", "cookies": "ltuid_v2=2" },
+            ],
+        })
+        .to_string(),
+    )
+    .expect("Writable temp dir");
+
+    let error = crate::load_config(&path, None)
+        .err()
+        .expect("Invalid config");
+    std::fs::remove_file(&path).ok();
+
+    assert!(error
+        .to_string()
+        .contains("accounts[1].cookies: invalid type: string \"ltuid_v2=2\", expected a map"));
+}
+
+#[test]
+fn colors_the_summary_table_by_status() {
+    let client = MockClient::default().on(INFO, 200, SIGNED);
+    let report = [single(&config(json!({})), &client)];
+
+    assert_eq!(
+        crate::report::summary_table(&report, false),
+        "Account | Genshin Impact\nalice   | ✓ done"
+    );
+    assert_eq!(
+        crate::report::summary_table(&report, true),
+        "Account | Genshin Impact\nalice   | \x1b[32m✓ done\x1b[0m"
+    );
+}
+
+#[test]
+fn breaker_skips_a_game_after_failures_in_a_row() {
+    let config = config(json!({}));
+    let down = || run(&config, &MockClient::default().on(INFO, 502, ""));
+    let mut breaker = CircuitBreaker::new(2);
+
+    breaker.record(&Genshin, &down());
+    breaker.record(
+        &Genshin,
+        &run(&config, &MockClient::default().on(INFO, 200, SIGNED)),
+    );
+    breaker.record(&Genshin, &down());
+    assert!(!breaker.is_open(&Genshin), "A response resets the count");
+
+    breaker.record(&Genshin, &down());
+    assert!(breaker.is_open(&Genshin));
+
+    let mut disabled = CircuitBreaker::new(0);
+    (0..3).for_each(|_| disabled.record(&Genshin, &down()));
+    assert!(!disabled.is_open(&Genshin));
+}
+
+#[test]
+fn discovers_act_ids_from_hoyolab_tools() {
+    let tools = json!({
+        "retcode": 0,
+        "data": { "modules": [{ "tools": [
+            { "name": "Map", "web_path": "https://act.hoyolab.com/ys/app/interactive-map/index.html" },
+            { "name": "Check-In", "web_path": "https://act.hoyolab.com/ys/event/signin-sea-v4/index.html?act_id=e2027" },
+        ]}]},
+    });
+    let client = MockClient::default()
+        .on("/guide/material", 200, &tools.to_string())
+        .on(
+            "/guide/material",
+            200,
+            r#"{"retcode":-1,"message":"error"}"#,
+        )
+        .on("/guide/material", 502, "");
+
+    let discovered = client::block_on(crate::discovery::discover(&client, None));
+
+    assert_eq!(discovered.len(), 1, "Failed lookups fall back");
+    assert_eq!(discovered["genshin"].act_id, "e2027");
+    assert!(client.requests("/guide/material")[0]
+        .uri()
+        .query()
+        .is_some_and(|query| query.contains("game_id=2")));
+}
+
+#[test]
+fn reads_cookies_from_vault() {
+    let config: crate::vault::VaultConfig = serde_json::from_value(json!({
+        "address": "https://vault.example.com/",
+        "approle": { "role_id": "role", "secret_id": "secret" },
+    }))
+    .expect("Valid vault config");
+    let client = MockClient::default()
+        .on(
+            "/v1/auth/approle/login",
+            200,
+            r#"{"auth":{"client_token":"s.token"}}"#,
+        )
+        .on(
+            "/v1/secret/data/hoyolab/alice",
+            200,
+            r#"{"data":{"data":{"ltuid_v2":"1","ltoken_v2":"secret"},"metadata":{"version":2}}}"#,
+        )
+        .on(
+            "/v1/secret/data/hoyolab/bob",
+            403,
+            r#"{"errors":["permission denied"]}"#,
+        );
+
+    let vault = client::block_on(crate::vault::login(&client, &config)).expect("Logged in");
+    let cookies =
+        client::block_on(vault.cookies(&client, "hoyolab/alice")).expect("Cookies in the secret");
+    let denied = client::block_on(vault.cookies(&client, "/hoyolab/bob/"));
+
+    assert_eq!(cookies["ltuid_v2"], "1");
+    assert_eq!(cookies["ltoken_v2"], "secret");
+    assert_eq!(
+        denied.err().map(|e| e.to_string()),
+        Some("HTTP 403 Forbidden: permission denied".to_string())
+    );
+    assert_eq!(
+        client.requests("/v1/secret/data/hoyolab/alice")[0].headers()["x-vault-token"],
+        "s.token"
+    );
+}
+
+#[test]
+fn signs_aws_requests() {
+    // The get-vanilla case of AWS's Signature Version 4 test suite.
+    let credentials: crate::aws::Credentials = serde_json::from_value(json!({
+        "AccessKeyId": "AKIDEXAMPLE",
+        "SecretAccessKey": "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+    }))
+    .expect("Valid credentials");
+    let mut request = http::Request::get("https://example.amazonaws.com/")
+        .body(Vec::new())
+        .expect("Valid request");
+
+    crate::aws::sign(
+        &mut request,
+        &credentials,
+        "us-east-1",
+        "service",
+        "20150830T123600Z",
+    )
+    .expect("Signed");
+
+    assert_eq!(
+        request.headers()["authorization"],
+        "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+         SignedHeaders=host;x-amz-date, \
+         Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+    );
+}
+
+#[test]
+fn resolves_aws_references() {
+    let mut config = json!({
+        "accounts": [{
+            "name": "alice",
+            "cookies": "aws:arn:aws:secretsmanager:eu-west-1:123456789012:secret:hoyolab/alice",
+        }],
+        "notifiers": [{
+            "type": "discord",
+            "webhook_url": "aws:arn:aws:ssm:eu-west-1:123456789012:parameter/hoyolab/discord",
+        }],
+    });
+    let client = MockClient::default()
+        .on(
+            "/",
+            200,
+            &json!({ "SecretString": r#"{"ltuid_v2":"1","ltoken_v2":"secret"}"# }).to_string(),
+        )
+        .on(
+            "/",
+            200,
+            r#"{"Parameter":{"Value":"https://discord.com/api/webhooks/1/token"}}"#,
+        );
+
+    assert!(crate::aws::references(&config));
+    with_env(
+        &[
+            ("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+        ],
+        || client::block_on(crate::aws::resolve(&client, &mut config)),
+    )
+    .expect("Resolved");
+
+    assert_eq!(config["accounts"][0]["cookies"]["ltoken_v2"], "secret");
+    assert_eq!(
+        config["notifiers"][0]["webhook_url"],
+        "https://discord.com/api/webhooks/1/token"
+    );
+
+    let requests = client.requests("/");
+    assert_eq!(
+        requests[0].uri().host(),
+        Some("secretsmanager.eu-west-1.amazonaws.com")
+    );
+    assert_eq!(
+        requests[1].headers()["x-amz-target"],
+        "AmazonSSM.GetParameter"
+    );
+}
+
+#[test]
+fn detects_the_games_an_account_plays() {
+    let cards = json!({
+        "retcode": 0,
+        "data": { "list": [
+            { "game_id": 2, "has_role": true, "level": 60 },
+            { "game_id": 6, "has_role": false },
+        ]},
+    });
+    let client = MockClient::default().on("/getGameRecordCard", 200, &cards.to_string());
+    let config = config(json!({}));
+    let checkin = HoyolabCheckin::new(&config, &config.accounts[0], &client, &[], false, false);
+
+    let played = client::block_on(checkin.played_games()).expect("Cards are listed");
+
+    assert_eq!(played, Some(vec![2]));
+    assert!(client.requests("/getGameRecordCard")[0]
+        .uri()
+        .query()
+        .is_some_and(|query| query.contains("uid=1")));
+}
+
+#[test]
+fn renders_healthcheck_templates() {
+    let values = [("status", "down"), ("summary", "a \"b\"\nc")];
+
+    assert_eq!(
+        crate::healthcheck::render("/push?status={status}&msg={summary}", &values, |value| {
+            url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+        }),
+        "/push?status=down&msg=a+%22b%22%0Ac"
+    );
+    assert_eq!(
+        crate::healthcheck::render("{\"up\": \"{status}\"}", &values, str::to_string),
+        "{\"up\": \"down\"}"
+    );
+}
+
+#[test]
+fn refuses_a_second_run_lock() {
+    let path = std::env::temp_dir().join(format!("hoyo-checkin-test-{}.lock", std::process::id()));
+
+    let lock = crate::lock::acquire(&path);
+    let second = crate::lock::acquire(&path);
+
+    assert!(lock.is_ok());
+    assert!(matches!(
+        second,
+        Err(crate::lock::LockError::Held { pid: Some(pid), .. }) if pid == std::process::id()
+    ));
+
+    drop(lock);
+    assert!(
+        crate::lock::acquire(&path).is_ok(),
+        "Released with the lock"
+    );
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn collects_stats_within_the_window() {
+    let now = 1_790_000_000;
+    let attempt = |days_ago: u64, status: Status, duration_ms: Option<u64>| Attempt {
+        started_at: clock::rfc3339(now - days_ago * clock::DAY),
+        server_date: clock::server_date(now - days_ago * clock::DAY),
+        account: "alice".to_string(),
+        game: Genshin.name().to_string(),
+        kind: RewardKind::Daily,
+        status,
+        retcode: None,
+        reward: None,
+        duration_ms,
+    };
+    let history = [
+        attempt(10, Status::Failed, None),
+        attempt(3, Status::CaptchaRequired, Some(3000)),
+        attempt(2, Status::Claimed, Some(1000)),
+        attempt(1, Status::AlreadyClaimed, None),
+    ];
+
+    let stats = crate::stats::collect(
+        history.iter(),
+        &[&Genshin],
+        ("7d", Duration::from_secs(7 * clock::DAY)),
+        now,
+    );
+
+    assert_eq!(stats[0].attempts, 3);
+    assert_eq!(stats[0].succeeded, 2);
+    assert_eq!(stats[0].captchas, 1);
+    assert_eq!(stats[0].average_ms, Some(2000));
+    assert!(crate::stats::table(&stats)
+        .ends_with("Genshin Impact  3         66.7%      33.3%     2.0s"));
+}
+
+#[test]
+fn generates_systemd_units_for_the_schedule() {
+    let schedule = crate::daemon::Schedule::parse("reset+15m").expect("Valid schedule");
+    let (service, timer) = crate::systemd::units(
+        &[
+            "/usr/bin/hoyo-checkin-rs",
+            "--config",
+            "/srv/my hoyo/config.json",
+        ],
+        std::path::Path::new("/srv/my hoyo"),
+        Some("alice"),
+        Some(&schedule),
+    );
+
+    assert!(service.contains("User=alice\n"));
+    assert!(service.contains("WorkingDirectory=/srv/my hoyo\n"));
+    assert!(service
+        .contains("ExecStart=/usr/bin/hoyo-checkin-rs --config \"/srv/my hoyo/config.json\"\n"));
+    assert!(timer.contains("OnCalendar=*-*-* 16:15:00 UTC\n"));
+
+    let (_, timer) = crate::systemd::units(&[], std::path::Path::new("/"), None, None);
+    assert!(timer.contains("OnCalendar=*-*-* 16:00:00 UTC\n"));
+}
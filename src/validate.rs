@@ -0,0 +1,108 @@
+use crate::{client::HttpClient, CheckinError, HoyolabCheckin, SignResponse, GAMES};
+use http::{HeaderValue, Method};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Returns the logged-in HoYoLAB user, or a "not logged in" retcode when the
+/// cookies are no longer valid. Cheap, and independent of any game.
+const USER_INFO_URL: &str = "https://bbs-api-os.hoyolab.com/community/user/wapi/getUserFullInfo";
+
+#[derive(Deserialize)]
+struct UserInfoData {
+    user_info: Option<UserInfo>,
+}
+
+#[derive(Deserialize)]
+struct UserInfo {
+    nickname: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CookieStatus {
+    Valid {
+        nickname: Option<String>,
+    },
+    Expired,
+    Malformed {
+        reason: String,
+    },
+    /// The cookies could not be checked, e.g. because HoYoLAB is unreachable.
+    Unknown {
+        reason: String,
+    },
+}
+
+#[derive(Serialize)]
+pub struct CookieReport {
+    pub account: String,
+    #[serde(flatten)]
+    pub status: CookieStatus,
+}
+
+impl<C: HttpClient> HoyolabCheckin<'_, C> {
+    /// Checks the account's cookies without checking in.
+    pub async fn validate_cookies(&self) -> CookieReport {
+        let status = match malformed(&self.account.cookies) {
+            Some(reason) => CookieStatus::Malformed { reason },
+            None => match self.user_info().await {
+                Ok(nickname) => CookieStatus::Valid { nickname },
+                Err(CheckinError::CookieExpired { .. }) => CookieStatus::Expired,
+                Err(e) => CookieStatus::Unknown {
+                    reason: e.to_string(),
+                },
+            },
+        };
+
+        CookieReport {
+            account: self.account.name.clone(),
+            status,
+        }
+    }
+
+    async fn user_info(&self) -> Result<Option<String>, CheckinError> {
+        let request = self.request(
+            GAMES[0],
+            Method::GET,
+            USER_INFO_URL,
+            &[("lang", self.lang())],
+            Vec::new(),
+        )?;
+        let response: SignResponse<UserInfoData> = self.send(request).await?;
+        let return_code = response.retcode.unwrap_or(0);
+
+        if return_code != 0 {
+            return Err(CheckinError::api(return_code, response.message));
+        }
+
+        Ok(response
+            .data
+            .and_then(|data| data.user_info)
+            .and_then(|info| info.nickname))
+    }
+}
+
+/// Problems that can be spotted without asking HoYoLAB.
+pub fn malformed(cookies: &HashMap<String, String>) -> Option<String> {
+    for (required, legacy) in [("ltoken_v2", "ltoken"), ("ltuid_v2", "ltuid")] {
+        if !cookies.contains_key(required) && !cookies.contains_key(legacy) {
+            return Some(format!("missing {} cookie", required));
+        }
+    }
+
+    cookies
+        .iter()
+        .find(|(_, value)| value.is_empty())
+        .map(|(name, _)| name)
+        .or_else(|| unsendable(cookies))
+        .map(|name| format!("invalid value for {} cookie", name))
+}
+
+/// The first cookie that can't go in a `Cookie` header, e.g. for a control
+/// character in its value.
+pub fn unsendable(cookies: &HashMap<String, String>) -> Option<&String> {
+    cookies
+        .iter()
+        .find(|(name, value)| HeaderValue::from_str(&format!("{}={}", name, value)).is_err())
+        .map(|(name, _)| name)
+}
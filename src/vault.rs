@@ -0,0 +1,149 @@
+//! `vault`: account cookies kept in HashiCorp Vault's KV secrets engine
+//! instead of on disk, read every time the config is loaded.
+
+use crate::client::{self, HttpClient, Request};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, env};
+
+#[derive(Deserialize)]
+pub struct VaultConfig {
+    /// e.g. `https://vault.example.com:8200`.
+    address: String,
+    /// Falls back to `VAULT_TOKEN` when neither this nor `approle` is set.
+    token: Option<String>,
+    /// Logs in with an AppRole instead of a fixed token.
+    approle: Option<AppRole>,
+    /// Vault Enterprise namespace the secrets are in.
+    namespace: Option<String>,
+    /// Mount of the KV version 2 engine, `secret` by default.
+    #[serde(default = "default_mount")]
+    mount: String,
+}
+
+#[derive(Deserialize)]
+struct AppRole {
+    role_id: String,
+    secret_id: String,
+}
+
+fn default_mount() -> String {
+    "secret".to_string()
+}
+
+/// Logged in to Vault, ready to read secrets.
+pub struct Vault<'a> {
+    config: &'a VaultConfig,
+    token: String,
+}
+
+pub async fn login<'a, C: HttpClient>(
+    client: &C,
+    config: &'a VaultConfig,
+) -> Result<Vault<'a>, client::Error> {
+    let token = match (&config.approle, &config.token) {
+        (Some(approle), _) => {
+            let body = call(
+                client,
+                config,
+                None,
+                http::Method::POST,
+                "auth/approle/login",
+                json!({ "role_id": approle.role_id, "secret_id": approle.secret_id }),
+            )
+            .await?;
+
+            body["auth"]["client_token"]
+                .as_str()
+                .ok_or("No client_token in the AppRole login response")?
+                .to_string()
+        }
+        (None, Some(token)) => token.clone(),
+        (None, None) => env::var("VAULT_TOKEN")
+            .map_err(|_| "No token, approle or VAULT_TOKEN to log in with")?,
+    };
+
+    Ok(Vault { config, token })
+}
+
+impl Vault<'_> {
+    /// The cookies stored at `path`, one per key of the secret, e.g.
+    /// `ltuid_v2` and `ltoken_v2`.
+    pub async fn cookies<C: HttpClient>(
+        &self,
+        client: &C,
+        path: &str,
+    ) -> Result<BTreeMap<String, String>, client::Error> {
+        let body = call(
+            client,
+            self.config,
+            Some(&self.token),
+            http::Method::GET,
+            &format!("{}/data/{}", self.config.mount, path.trim_matches('/')),
+            Value::Null,
+        )
+        .await?;
+
+        let cookies: BTreeMap<String, String> = body["data"]["data"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+            .collect();
+
+        if cookies.is_empty() {
+            return Err(format!("No cookies in the secret {}", path).into());
+        }
+
+        Ok(cookies)
+    }
+}
+
+async fn call<C: HttpClient>(
+    client: &C,
+    config: &VaultConfig,
+    token: Option<&str>,
+    method: http::Method,
+    path: &str,
+    body: Value,
+) -> Result<Value, client::Error> {
+    let mut request = http::Request::builder().method(method).uri(format!(
+        "{}/v1/{}",
+        config.address.trim_end_matches('/'),
+        path
+    ));
+
+    if let Some(token) = token {
+        request = request.header("X-Vault-Token", token);
+    }
+
+    if let Some(namespace) = &config.namespace {
+        request = request.header("X-Vault-Namespace", namespace);
+    }
+
+    let request: Request = match body {
+        Value::Null => request.body(Vec::new())?,
+        body => request
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&body)?)?,
+    };
+    let response = client.execute(request).await?;
+    let body: Value = serde_json::from_slice(response.body()).unwrap_or_default();
+
+    if !response.status().is_success() {
+        let errors: Vec<&str> = body["errors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+
+        return Err(if errors.is_empty() {
+            format!("HTTP {}", response.status()).into()
+        } else {
+            format!("HTTP {}: {}", response.status(), errors.join(", ")).into()
+        });
+    }
+
+    Ok(body)
+}